@@ -1,44 +1,369 @@
 
+use std::mem;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use crate::scan_term::ScanTerm;
-use crate::captures::{Captures, NoneCapture};
+use crate::haystack::Haystack;
+use crate::captures::{Captures, NoneCapture, Span};
 
-pub struct CaptureResult<'a, T> {
-    pub captures: Captures<'a, T>,
-    pub rest: &'a str,
+pub struct CaptureResult<'a, T, H: ?Sized = str> {
+    pub captures: Captures<'a, T, H>,
+    pub rest: &'a H,
 }
 
-impl<'a, T> CaptureResult<'a, T> {
-    pub fn new(captures: Captures<'a, T>, rest: &'a str) -> CaptureResult<'a, T> {
+impl<'a, T, H: ?Sized + Haystack> CaptureResult<'a, T, H> {
+    pub fn new(captures: Captures<'a, T, H>, rest: &'a H) -> CaptureResult<'a, T, H> {
         CaptureResult {
             captures,
             rest,
         }
     }
+
+    /// Byte offsets of the match in the whole input
+    pub fn span(&self) -> Span {
+        self.captures.span()
+    }
+}
+
+impl<'a, T, H: ?Sized> From<CaptureResult<'a, T, H>> for (Captures<'a, T, H>, &'a H) {
+    fn from(res: CaptureResult<'a, T, H>) -> Self {
+        (res.captures, res.rest)
+    }
+}
+
+/// Unique identifier of a capture group
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct GroupId(usize);
+
+impl GroupId {
+    pub fn new() -> GroupId {
+        static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+        GroupId(NEXT_ID.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+impl Default for GroupId {
+    fn default() -> Self {
+        GroupId::new()
+    }
 }
 
-impl<'a, T> Into<(Captures<'a, T>, &'a str)> for CaptureResult<'a, T> {
-    fn into(self) -> (Captures<'a, T>, &'a str) {
-        (self.captures, self.rest)
+/// State of the matching shared by all patterns of expression
+///
+/// The context holds the whole input, so patterns can look
+/// at the text before the current position.
+/// It also holds groups captured so far,
+/// so patterns can refer to the text matched on the left of them.
+/// Finally it holds the matching mode, like case insensitivity.
+///
+/// A partial context holds only a beginning of the input,
+/// patterns note when their result depends on the text after the end of it.
+///
+pub struct Context<'a, H: ?Sized = str> {
+    text: &'a H,
+    groups: Vec<(GroupId, &'a H)>,
+    nocase: bool,
+    partial: bool,
+    hit_end: bool,
+}
+
+impl<'a, H: ?Sized> Clone for Context<'a, H> {
+    fn clone(&self) -> Self {
+        Context {
+            text: self.text,
+            groups: self.groups.clone(),
+            nocase: self.nocase,
+            partial: self.partial,
+            hit_end: self.hit_end,
+        }
+    }
+}
+
+impl<'a, H: ?Sized + Haystack> Context<'a, H> {
+    pub fn new(text: &'a H) -> Context<'a, H> {
+        Context {
+            text,
+            groups: Vec::new(),
+            nocase: false,
+            partial: false,
+            hit_end: false,
+        }
+    }
+
+    /// Context of a text which is a beginning of the input
+    ///
+    /// More input may follow the text, e.g. when it's read from a stream.
+    ///
+    pub fn partial(text: &'a H) -> Context<'a, H> {
+        Context {
+            partial: true,
+            ..Context::new(text)
+        }
+    }
+
+    /// Checks more input may follow the text
+    pub fn is_partial(&self) -> bool {
+        self.partial
+    }
+
+    /// Notes the result of matching depends on the input after the end of the text
+    ///
+    /// Takes effect only if the context is partial.
+    ///
+    pub fn mark_end(&mut self) {
+        self.hit_end |= self.partial;
+    }
+
+    /// Checks some pattern looked past the end of the partial text
+    ///
+    /// If so, the result of matching may change when more input arrives.
+    ///
+    pub fn hit_end(&self) -> bool {
+        self.hit_end
+    }
+
+    pub fn reset_hit_end(&mut self) {
+        self.hit_end = false;
+    }
+
+    /// Checks terms are matched ignoring case
+    pub fn nocase(&self) -> bool {
+        self.nocase
+    }
+
+    /// Runs `f` with the case insensitivity mode
+    pub fn with_nocase<R>(&mut self, nocase: bool, f: impl FnOnce(&mut Self) -> R) -> R {
+        let current = mem::replace(&mut self.nocase, nocase);
+        let res = f(self);
+        self.nocase = current;
+        res
+    }
+
+    /// The last text captured by the group
+    pub fn group(&self, id: GroupId) -> Option<&'a H> {
+        self.groups
+            .iter()
+            .rev()
+            .find(|(group, _)| *group == id)
+            .map(|(_, captured)| *captured)
+    }
+
+    /// Runs `f` with the group captured
+    ///
+    /// The group is forgotten when `f` returns,
+    /// so a backtracked group never leaks into another branch.
+    ///
+    pub fn with_group<R>(&mut self, id: GroupId, captured: &'a H, f: impl FnOnce(&mut Self) -> R) -> R {
+        self.groups.push((id, captured));
+        let res = f(self);
+        self.groups.pop();
+        res
+    }
+
+    /// Number of groups captured so far, the mark to return to with `truncate_groups`
+    pub fn group_mark(&self) -> usize {
+        self.groups.len()
+    }
+
+    /// Copy of the groups captured after the mark
+    pub fn groups_since(&self, mark: usize) -> Vec<(GroupId, &'a H)> {
+        self.groups[mark..].to_vec()
+    }
+
+    /// Captures the groups until the context is truncated to a mark before them
+    ///
+    /// Unlike `with_group`, the groups outlive the call,
+    /// so a pattern can keep its state on the heap instead of the call stack.
+    ///
+    pub fn push_groups(&mut self, groups: Vec<(GroupId, &'a H)>) {
+        self.groups.extend(groups);
+    }
+
+    /// Forgets the groups captured after the mark
+    pub fn truncate_groups(&mut self, mark: usize) {
+        self.groups.truncate(mark);
+    }
+
+    /// Copy of all groups captured so far
+    pub fn saved_groups(&self) -> Vec<(GroupId, &'a H)> {
+        self.groups.clone()
+    }
+
+    /// Runs `f` with previously saved groups
+    pub fn with_saved_groups<R>(
+        &mut self,
+        groups: Vec<(GroupId, &'a H)>,
+        f: impl FnOnce(&mut Self) -> R,
+    ) -> R {
+        let current = mem::replace(&mut self.groups, groups);
+        let res = f(self);
+        self.groups = current;
+        res
+    }
+
+    /// Position (in bytes) of the rest of text in the whole input
+    ///
+    /// The rest is always a suffix of the input.
+    ///
+    pub fn position(&self, rest: &'a H) -> usize {
+        self.text.len() - rest.len()
+    }
+
+    /// The text before the rest
+    pub fn before(&self, rest: &'a H) -> &'a H {
+        self.text.split_at(self.position(rest)).0
+    }
+
+    /// The text starting from the position (in bytes)
+    pub fn rest_at(&self, position: usize) -> &'a H {
+        self.text.split_at(position).1
     }
 }
 
-pub trait Capture<'a> {
+/// Callback which receives a match of pattern and the context of it
+pub type CaptureFn<'f, 'a, T, H = str> = dyn FnMut(&mut Context<'a, H>, CaptureResult<'a, T, H>) -> bool + 'f;
+
+pub trait Capture<'a, H: ?Sized + Haystack = str> {
     type Inner: Clone;
-    fn capture(&self, text: &'a str) -> Option<CaptureResult<'a, Self::Inner>>;
-    fn capture_empty(&self, text: &'a str) -> CaptureResult<'a, Self::Inner>;
+
+    /// Returns the first (most preferred) match of the pattern
+    fn capture(&self, ctx: &mut Context<'a, H>, text: &'a H) -> Option<CaptureResult<'a, Self::Inner, H>> {
+        let mut first = None;
+        self.capture_each(ctx, text, &mut |_, res| {
+            first = Some(res);
+            true
+        });
+        first
+    }
+
+    /// Enumerates every match of the pattern at the beginning of the text
+    ///
+    /// Matches are passed to `f` in order of preference.
+    /// The enumeration stops as soon as `f` accepts a match by returning `true`,
+    /// in that case `capture_each` returns `true` too.
+    /// If `f` rejects every match the result is `false`.
+    fn capture_each(
+        &self,
+        ctx: &mut Context<'a, H>,
+        text: &'a H,
+        f: &mut CaptureFn<'_, 'a, Self::Inner, H>,
+    ) -> bool;
+
+    fn capture_empty(&self, ctx: &Context<'a, H>, text: &'a H) -> CaptureResult<'a, Self::Inner, H>;
+
+    /// Checks the pattern yields at most one match and leaves no groups in the context
+    ///
+    /// A repetition of such a pattern doesn't match it again
+    /// to look for another match when it backtracks into an iteration.
+    ///
+    fn is_simple(&self) -> bool {
+        false
+    }
+
+    /// Maximum number of chars (bytes for binary data) the pattern can match
+    ///
+    /// `None` means the width is unbounded or unknown.
+    ///
+    fn max_width(&self) -> Option<usize> {
+        None
+    }
+
+    /// Maximum number of chars (bytes for binary data) before the position the pattern looks at
+    ///
+    /// Anchors look at one char before, lookbehinds look as far as their pattern can match.
+    /// `None` means the pattern can look at the whole text before or it's unknown.
+    ///
+    fn max_behind(&self) -> Option<usize> {
+        None
+    }
+}
+
+fn capture_term<'a, H, S>(
+    term: &S,
+    ctx: &mut Context<'a, H>,
+    text: &'a H,
+    f: &mut CaptureFn<'_, 'a, NoneCapture, H>,
+) -> bool where
+    H: ?Sized + Haystack,
+    S: ScanTerm<H> + ?Sized,
+{
+    let scanned = if ctx.nocase() {
+        term.scan_term_nocase(text)
+    } else {
+        term.scan_term(text)
+    };
+
+    if ctx.is_partial() && term.is_incomplete(text, ctx.nocase()) {
+        ctx.mark_end();
+    }
+
+    match scanned.map(|len| text.split_at(len)) {
+        Some((left, rest)) => {
+            let caps = Captures::without_capture(ctx.position(text), left);
+            f(ctx, CaptureResult::new(caps, rest))
+        },
+        None => false,
+    }
+}
+
+fn capture_term_empty<'a, H: ?Sized + Haystack>(ctx: &Context<'a, H>, text: &'a H) -> CaptureResult<'a, NoneCapture, H> {
+    let caps = Captures::without_capture(ctx.position(text), text.split_at(0).0);
+    CaptureResult::new(caps, text)
 }
 
 impl<'a, S: ScanTerm> Capture<'a> for S {
     type Inner = NoneCapture;
 
-    fn capture(&self, text: &'a str) -> Option<CaptureResult<'a, Self::Inner>> {
-        let (left, rest) = self.scan_split(text)?;
-        let caps = Captures::without_capture(left);
-        Some(CaptureResult::new(caps, rest))
+    fn capture_each(
+        &self,
+        ctx: &mut Context<'a>,
+        text: &'a str,
+        f: &mut CaptureFn<'_, 'a, Self::Inner>,
+    ) -> bool {
+        capture_term(self, ctx, text, f)
+    }
+
+    fn capture_empty(&self, ctx: &Context<'a>, text: &'a str) -> CaptureResult<'a, Self::Inner> {
+        capture_term_empty(ctx, text)
+    }
+
+    fn is_simple(&self) -> bool {
+        true
+    }
+
+    fn max_width(&self) -> Option<usize> {
+        self.term_width()
+    }
+
+    fn max_behind(&self) -> Option<usize> {
+        Some(0)
+    }
+}
+
+impl<'a, S: ScanTerm<[u8]>> Capture<'a, [u8]> for S {
+    type Inner = NoneCapture;
+
+    fn capture_each(
+        &self,
+        ctx: &mut Context<'a, [u8]>,
+        text: &'a [u8],
+        f: &mut CaptureFn<'_, 'a, Self::Inner, [u8]>,
+    ) -> bool {
+        capture_term(self, ctx, text, f)
+    }
+
+    fn capture_empty(&self, ctx: &Context<'a, [u8]>, text: &'a [u8]) -> CaptureResult<'a, Self::Inner, [u8]> {
+        capture_term_empty(ctx, text)
+    }
+
+    fn is_simple(&self) -> bool {
+        true
+    }
+
+    fn max_width(&self) -> Option<usize> {
+        self.term_width()
     }
 
-    fn capture_empty(&self, text: &'a str) -> CaptureResult<'a, Self::Inner> {
-        let caps = Captures::without_capture("");
-        CaptureResult::new(caps, text)
+    fn max_behind(&self) -> Option<usize> {
+        Some(0)
     }
 }
@@ -1,20 +1,55 @@
 
+use crate::haystack::Haystack;
+use super::groups::Groups;
+use super::output::Output;
+use super::span::Span;
+
 #[derive(Copy, Clone, Debug)]
 pub enum EitherCaptures<A, B> {
     Left(A),
     Right(B),
 }
 
-impl<'a, A, B> Iterator for EitherCaptures<A, B> where
-    A: Iterator<Item=&'a str>,
-    B: Iterator<Item=&'a str>,
+impl<'a, H, A, B> Groups<'a, H> for EitherCaptures<A, B> where
+    H: ?Sized + Haystack,
+    A: Groups<'a, H>,
+    B: Groups<'a, H>,
+{
+    fn named(&self, name: &str) -> Option<&'a H> {
+        match self {
+            EitherCaptures::Left(a) => a.named(name),
+            EitherCaptures::Right(b) => b.named(name),
+        }
+    }
+
+    fn group_spans(&self, spans: &mut Vec<Span>) {
+        match self {
+            EitherCaptures::Left(a) => a.group_spans(spans),
+            EitherCaptures::Right(b) => b.group_spans(spans),
+        }
+    }
+
+    fn next_group(&mut self) -> Option<&'a H> {
+        match self {
+            EitherCaptures::Left(a) => a.next_group(),
+            EitherCaptures::Right(b) => b.next_group(),
+        }
+    }
+}
+
+/// An alternation produces the value of the matched branch,
+/// so both branches must produce values of the same type
+impl<'a, H, A, B> Output<'a, H> for EitherCaptures<A, B> where
+    H: ?Sized + Haystack,
+    A: Output<'a, H>,
+    B: Output<'a, H, Value = A::Value>,
 {
-    type Item = &'a str;
+    type Value = A::Value;
 
-    fn next(&mut self) -> Option<Self::Item> {
+    fn output(&self, captured: &'a H) -> Option<Self::Value> {
         match self {
-            EitherCaptures::Left(a) => a.next(),
-            EitherCaptures::Right(b) => b.next(),
+            EitherCaptures::Left(a) => a.output(captured),
+            EitherCaptures::Right(b) => b.output(captured),
         }
     }
 }
@@ -1,5 +1,9 @@
 
+use crate::haystack::Haystack;
 use super::none_capture::NoneCapture;
+use super::groups::Groups;
+use super::output::Output;
+use super::span::Span;
 
 #[derive(Copy, Clone, Debug)]
 enum CaptureIteratorState {
@@ -7,47 +11,158 @@ enum CaptureIteratorState {
     Inner,
 }
 
-#[derive(Copy, Clone, Debug)]
-pub struct Captures<'a, T> {
+/// Captured text of a match along with its groups
+///
+/// Iterates over the captured text and then over the text of every group.
+///
+#[derive(Debug)]
+pub struct Captures<'a, T, H: ?Sized = str> {
     iter_state: CaptureIteratorState,
-    pub captured_str: &'a str,
+    pub name: Option<&'static str>,
+    pub start: usize,
+    pub captured_str: &'a H,
     pub inner: T,
 }
 
-impl<'a, T> Captures<'a, T> {
-    pub fn new(matched_str: &str, inner: T) -> Captures<T> {
+// Derives would require `H: Clone`, which is not true for unsized text
+impl<'a, T: Clone, H: ?Sized> Clone for Captures<'a, T, H> {
+    fn clone(&self) -> Self {
+        Captures {
+            iter_state: self.iter_state,
+            name: self.name,
+            start: self.start,
+            captured_str: self.captured_str,
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<'a, T: Copy, H: ?Sized> Copy for Captures<'a, T, H> {}
+
+impl<'a, T, H: ?Sized + Haystack> Captures<'a, T, H> {
+    pub fn new(start: usize, matched_str: &'a H, inner: T) -> Captures<'a, T, H> {
         Captures {
             iter_state: CaptureIteratorState::CapturedStr,
+            name: None,
+            start,
             captured_str: matched_str,
             inner,
         }
     }
 
-    pub fn with_inner<K>(&self, inner: K) -> Captures<'a, K> {
+    pub fn with_inner<K>(&self, inner: K) -> Captures<'a, K, H> {
         Captures {
             iter_state: CaptureIteratorState::CapturedStr,
+            name: None,
+            start: self.start,
             captured_str: self.captured_str,
             inner,
         }
     }
 
+    pub fn with_name(self, name: Option<&'static str>) -> Captures<'a, T, H> {
+        Captures {
+            name,
+            ..self
+        }
+    }
+
     pub fn captured_len(&self) -> usize {
         self.captured_str.len()
     }
+
+    /// Byte offsets of the captured text in the whole input
+    ///
+    /// # Examples
+    /// ```
+    /// # use rep::{Pattern, ScanTerm};
+    /// let pattern = Pattern("world");
+    ///
+    /// let caps = pattern.matches("hello world").next().unwrap();
+    /// assert_eq!(caps.span().range(), 6..11);
+    /// ```
+    ///
+    pub fn span(&self) -> Span {
+        Span::new(self.start, self.start + self.captured_len())
+    }
+}
+
+impl<'a, T: Groups<'a, H>, H: ?Sized + Haystack> Captures<'a, T, H> {
+
+    /// Returns the text captured by the group with the name
+    ///
+    /// If several groups have the name, the leftmost one is returned.
+    ///
+    /// # Examples
+    /// ```
+    /// # use rep::{Pattern, ScanTerm, cap_named};
+    /// let digit = Pattern(|c: char| c.is_ascii_digit());
+    /// let date = cap_named("year", digit * 4) & '-' & cap_named("month", digit * 2);
+    ///
+    /// let caps = date.matches("2020-05").next().unwrap();
+    /// assert_eq!(caps.name("year"), Some("2020"));
+    /// assert_eq!(caps.name("month"), Some("05"));
+    /// assert_eq!(caps.name("day"), None);
+    /// ```
+    ///
+    pub fn name(&self, name: &str) -> Option<&'a H> {
+        self.named(name)
+    }
+
+    /// Returns spans of the whole match and of every group, in the order of iteration
+    ///
+    /// # Examples
+    /// ```
+    /// # use rep::{Pattern, ScanTerm, Span, cap};
+    /// let digit = Pattern(|c: char| c.is_ascii_digit());
+    /// let pattern = cap(digit * 2) & ':' & cap(digit * 2);
+    ///
+    /// let caps = pattern.matches("at 10:30").next().unwrap();
+    /// assert_eq!(caps.spans(), vec![Span::new(3, 8), Span::new(3, 5), Span::new(6, 8)]);
+    /// ```
+    ///
+    pub fn spans(&self) -> Vec<Span> {
+        let mut spans = Vec::new();
+        self.group_spans(&mut spans);
+        spans
+    }
 }
 
-impl Captures<'_, NoneCapture> {
-    pub fn without_capture(matched_str: &str) -> Captures<NoneCapture> {
+impl<'a, T: Output<'a, H>, H: ?Sized + Haystack> Captures<'a, T, H> {
+
+    /// Returns the value produced by the match
+    ///
+    /// It is the matched text for plain patterns
+    /// and the result of the transformation for mapped ones.
+    ///
+    /// # Examples
+    /// ```
+    /// # use rep::{Pattern, ScanTerm};
+    /// let number = (Pattern(char::is_numeric) * (1..)).map(|s| s.len());
+    ///
+    /// let caps = number.matches("x 123").next().unwrap();
+    /// assert_eq!(caps.value(), Some(3));
+    /// ```
+    ///
+    pub fn value(&self) -> Option<T::Value> {
+        self.inner.output(self.captured_str)
+    }
+}
+
+impl<'a, H: ?Sized + Haystack> Captures<'a, NoneCapture, H> {
+    pub fn without_capture(start: usize, matched_str: &'a H) -> Captures<'a, NoneCapture, H> {
         Captures {
             iter_state: CaptureIteratorState::CapturedStr,
+            name: None,
+            start,
             captured_str: matched_str,
             inner: NoneCapture,
         }
     }
 }
 
-impl<'a, T: Iterator<Item = &'a str>> Iterator for Captures<'a, T> {
-    type Item = &'a str;
+impl<'a, T: Groups<'a, H>, H: ?Sized + Haystack> Iterator for Captures<'a, T, H> {
+    type Item = &'a H;
 
     fn next(&mut self) -> Option<Self::Item> {
         match self.iter_state {
@@ -55,13 +170,47 @@ impl<'a, T: Iterator<Item = &'a str>> Iterator for Captures<'a, T> {
                 self.iter_state = CaptureIteratorState::Inner;
                 Some(self.captured_str)
             },
-            CaptureIteratorState::Inner => self.inner.next(),
+            CaptureIteratorState::Inner => self.inner.next_group(),
+        }
+    }
+}
+
+impl<'a, T: Groups<'a, H>, H: ?Sized + Haystack> Groups<'a, H> for Captures<'a, T, H> {
+    fn named(&self, name: &str) -> Option<&'a H> {
+        if self.name == Some(name) {
+            Some(self.captured_str)
+        } else {
+            self.inner.named(name)
         }
     }
+
+    fn group_spans(&self, spans: &mut Vec<Span>) {
+        spans.push(self.span());
+        self.inner.group_spans(spans);
+    }
+
+    fn next_group(&mut self) -> Option<&'a H> {
+        self.next()
+    }
+}
+
+/// A group produces the value of the grouped pattern
+impl<'a, T: Output<'a, H>, H: ?Sized + Haystack> Output<'a, H> for Captures<'a, T, H> {
+    type Value = T::Value;
+
+    fn output(&self, _: &'a H) -> Option<Self::Value> {
+        self.value()
+    }
+}
+
+impl<'a, T> From<Captures<'a, T>> for &'a str {
+    fn from(caps: Captures<'a, T>) -> Self {
+        caps.captured_str
+    }
 }
 
-impl<'a, T> Into<&'a str> for Captures<'a, T> {
-    fn into(self) -> &'a str {
-        self.captured_str
+impl<'a, T> From<Captures<'a, T, [u8]>> for &'a [u8] {
+    fn from(caps: Captures<'a, T, [u8]>) -> Self {
+        caps.captured_str
     }
 }
@@ -1,4 +1,9 @@
 
+use crate::haystack::Haystack;
+use super::groups::Groups;
+use super::output::Output;
+use super::span::Span;
+
 #[derive(Copy, Clone, Debug)]
 pub struct DoubleCaptures<A, B> {
     a: Option<A>,
@@ -14,24 +19,47 @@ impl<A, B> DoubleCaptures<A, B> {
     }
 }
 
-impl<'a, A, B> Iterator for DoubleCaptures<A, B> where
-    A: Iterator<Item=&'a str>,
-    B: Iterator<Item=&'a str>,
+impl<'a, H, A, B> Groups<'a, H> for DoubleCaptures<A, B> where
+    H: ?Sized + Haystack,
+    A: Groups<'a, H>,
+    B: Groups<'a, H>,
 {
-    type Item = &'a str;
+    fn named(&self, name: &str) -> Option<&'a H> {
+        self.a
+            .as_ref()
+            .and_then(|a| a.named(name))
+            .or_else(|| self.b.named(name))
+    }
+
+    fn group_spans(&self, spans: &mut Vec<Span>) {
+        if let Some(a) = &self.a {
+            a.group_spans(spans);
+        }
+
+        self.b.group_spans(spans);
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
+    fn next_group(&mut self) -> Option<&'a H> {
         match &mut self.a {
             Some(it) => {
-                match it.next() {
+                match it.next_group() {
                     a @ Some(_) => a,
                     None => {
                         self.a.take();
-                        self.b.next()
+                        self.b.next_group()
                     }
                 }
             },
-            None => self.b.next(),
+            None => self.b.next_group(),
         }
     }
 }
+
+/// A sequence produces the whole matched text
+impl<'a, H: ?Sized + Haystack, A, B> Output<'a, H> for DoubleCaptures<A, B> {
+    type Value = &'a H;
+
+    fn output(&self, captured: &'a H) -> Option<Self::Value> {
+        Some(captured)
+    }
+}
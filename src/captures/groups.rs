@@ -0,0 +1,16 @@
+
+use crate::haystack::Haystack;
+use super::span::Span;
+
+/// Walk through groups of nested captures
+pub trait Groups<'a, H: ?Sized + Haystack> {
+
+    /// Returns the text of the leftmost group with the name
+    fn named(&self, name: &str) -> Option<&'a H>;
+
+    /// Pushes spans of all groups in order
+    fn group_spans(&self, spans: &mut Vec<Span>);
+
+    /// Returns the text of the next group in order
+    fn next_group(&mut self) -> Option<&'a H>;
+}
@@ -0,0 +1,69 @@
+
+use crate::haystack::Haystack;
+use super::captures::Captures;
+use super::groups::Groups;
+use super::output::Output;
+use super::span::Span;
+
+/// Inner captures of `KeywordEntries` which hold the index of the matched entry
+///
+/// The index is missing if the keywords matched without scanning the text,
+/// e.g. they were repeated zero times.
+///
+#[derive(Copy, Clone, Debug)]
+pub struct KeywordEntry {
+    pub index: Option<usize>,
+}
+
+impl KeywordEntry {
+    pub fn new(index: usize) -> KeywordEntry {
+        KeywordEntry {
+            index: Some(index),
+        }
+    }
+
+    pub fn empty() -> KeywordEntry {
+        KeywordEntry {
+            index: None,
+        }
+    }
+}
+
+impl<'a, H: ?Sized> Captures<'a, KeywordEntry, H> {
+
+    /// Returns the index of the matched entry of keywords
+    ///
+    /// # Examples
+    /// ```
+    /// # use rep::{Keywords, nocase};
+    /// let sql = nocase(Keywords::new(&["select", "from", "where"]).capture_entries());
+    ///
+    /// let caps = sql.matches("FROM t").next().unwrap();
+    /// assert_eq!(caps.entry(), Some(1));
+    /// ```
+    ///
+    pub fn entry(&self) -> Option<usize> {
+        self.inner.index
+    }
+}
+
+/// Keywords have no groups
+impl<'a, H: ?Sized + Haystack> Groups<'a, H> for KeywordEntry {
+    fn named(&self, _: &str) -> Option<&'a H> {
+        None
+    }
+
+    fn group_spans(&self, _: &mut Vec<Span>) {}
+
+    fn next_group(&mut self) -> Option<&'a H> {
+        None
+    }
+}
+
+impl<'a, H: ?Sized + Haystack> Output<'a, H> for KeywordEntry {
+    type Value = &'a H;
+
+    fn output(&self, captured: &'a H) -> Option<Self::Value> {
+        Some(captured)
+    }
+}
@@ -0,0 +1,50 @@
+
+use crate::haystack::Haystack;
+use super::groups::Groups;
+use super::output::Output;
+use super::span::Span;
+
+/// Inner captures of a pattern which produces a value
+///
+/// The value is missing if the pattern matched without running
+/// the transformation, e.g. it was repeated zero times.
+///
+#[derive(Copy, Clone, Debug)]
+pub struct Mapped<T> {
+    pub value: Option<T>,
+}
+
+impl<T> Mapped<T> {
+    pub fn new(value: T) -> Mapped<T> {
+        Mapped {
+            value: Some(value),
+        }
+    }
+
+    pub fn empty() -> Mapped<T> {
+        Mapped {
+            value: None,
+        }
+    }
+}
+
+/// Mapped patterns have no groups
+impl<'a, H: ?Sized + Haystack, T> Groups<'a, H> for Mapped<T> {
+    fn named(&self, _: &str) -> Option<&'a H> {
+        None
+    }
+
+    fn group_spans(&self, _: &mut Vec<Span>) {}
+
+    fn next_group(&mut self) -> Option<&'a H> {
+        None
+    }
+}
+
+impl<'a, H: ?Sized + Haystack, T: Clone> Output<'a, H> for Mapped<T> {
+    type Value = T;
+
+    fn output(&self, _: &'a H) -> Option<Self::Value> {
+        self.value.clone()
+    }
+}
@@ -1,10 +1,23 @@
 
+#[allow(clippy::module_inception)]
 mod captures;
 mod double_captures;
 mod alter_captures;
 mod none_capture;
+mod groups;
+mod output;
+mod mapped;
+mod span;
+mod repeats;
+mod keyword_entry;
 
 pub use captures::Captures;
 pub use double_captures::DoubleCaptures;
 pub use alter_captures::EitherCaptures;
 pub use none_capture::NoneCapture;
+pub use repeats::Repeats;
+pub use mapped::Mapped;
+pub use keyword_entry::KeywordEntry;
+pub use output::Output;
+pub use groups::Groups;
+pub use span::Span;
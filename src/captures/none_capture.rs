@@ -1,11 +1,28 @@
 
+use crate::haystack::Haystack;
+use super::groups::Groups;
+use super::output::Output;
+use super::span::Span;
+
 #[derive(Copy, Clone, Debug)]
 pub struct NoneCapture;
 
-impl Iterator for NoneCapture {
-    type Item = &'static str;
+impl<'a, H: ?Sized + Haystack> Groups<'a, H> for NoneCapture {
+    fn named(&self, _: &str) -> Option<&'a H> {
+        None
+    }
+
+    fn group_spans(&self, _: &mut Vec<Span>) {}
 
-    fn next(&mut self) -> Option<Self::Item> {
+    fn next_group(&mut self) -> Option<&'a H> {
         None
     }
 }
+
+impl<'a, H: ?Sized + Haystack> Output<'a, H> for NoneCapture {
+    type Value = &'a H;
+
+    fn output(&self, captured: &'a H) -> Option<Self::Value> {
+        Some(captured)
+    }
+}
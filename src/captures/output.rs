@@ -0,0 +1,19 @@
+
+use crate::haystack::Haystack;
+
+/// Value produced by a match
+///
+/// Plain patterns produce the matched text.
+/// Patterns transformed with `Pattern::map` and `Pattern::and_then`
+/// produce the value returned by the transformation.
+///
+pub trait Output<'a, H: ?Sized + Haystack> {
+    type Value: Clone;
+
+    /// Returns the value of the match which captured the text
+    ///
+    /// `None` means the pattern took part in the match
+    /// without producing a value, e.g. it was repeated zero times.
+    ///
+    fn output(&self, captured: &'a H) -> Option<Self::Value>;
+}
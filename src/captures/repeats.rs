@@ -0,0 +1,94 @@
+
+use std::sync::Arc;
+use crate::haystack::Haystack;
+use super::captures::Captures;
+use super::groups::Groups;
+use super::output::Output;
+use super::span::Span;
+
+/// Captures of every iteration of a repetition
+///
+/// The iterations are shared with the repetition which matched them,
+/// they are copied only when groups of a shared copy are walked.
+///
+#[derive(Debug)]
+pub struct Repeats<'a, T, H: ?Sized = str> {
+    iterations: Arc<Vec<Captures<'a, T, H>>>,
+    current: usize,
+}
+
+impl<'a, T: Clone, H: ?Sized> Clone for Repeats<'a, T, H> {
+    fn clone(&self) -> Self {
+        Repeats {
+            iterations: Arc::clone(&self.iterations),
+            current: self.current,
+        }
+    }
+}
+
+impl<'a, T, H: ?Sized> Repeats<'a, T, H> {
+    pub fn new(iterations: Arc<Vec<Captures<'a, T, H>>>) -> Repeats<'a, T, H> {
+        Repeats {
+            iterations,
+            current: 0,
+        }
+    }
+}
+
+impl<'a, T: Clone, H: ?Sized> Captures<'a, Repeats<'a, T, H>, H> {
+
+    /// Returns captures of every iteration of the repetition
+    ///
+    /// The first item of each iteration is the text matched by the iteration,
+    /// followed by its groups.
+    ///
+    /// # Examples
+    /// ```
+    /// # use rep::{Pattern, ScanTerm, cap};
+    /// let item = cap(Pattern(char::is_numeric) * (1..));
+    /// let list = ((item & ',') * ..).repeated();
+    ///
+    /// let caps = list.matches("1,23,").next().unwrap();
+    /// let items: Vec<&str> = caps.repeated().map(|mut it| it.nth(1).unwrap()).collect();
+    /// assert_eq!(items, ["1", "23"]);
+    /// ```
+    ///
+    pub fn repeated(&self) -> impl Iterator<Item = Captures<'a, T, H>> + '_ {
+        self.inner.iterations.iter().cloned()
+    }
+}
+
+impl<'a, T: Groups<'a, H> + Clone, H: ?Sized + Haystack> Groups<'a, H> for Repeats<'a, T, H> {
+    fn named(&self, name: &str) -> Option<&'a H> {
+        self.iterations[self.current..]
+            .iter()
+            .find_map(|iteration| iteration.inner.named(name))
+    }
+
+    fn group_spans(&self, spans: &mut Vec<Span>) {
+        for iteration in &self.iterations[self.current..] {
+            iteration.inner.group_spans(spans);
+        }
+    }
+
+    /// Iterates over groups of every iteration in order
+    fn next_group(&mut self) -> Option<&'a H> {
+        let iterations = Arc::make_mut(&mut self.iterations);
+        while let Some(iteration) = iterations.get_mut(self.current) {
+            match iteration.inner.next_group() {
+                group @ Some(_) => return group,
+                None => self.current += 1,
+            }
+        }
+
+        None
+    }
+}
+
+impl<'a, T, H: ?Sized + Haystack> Output<'a, H> for Repeats<'a, T, H> {
+    type Value = &'a H;
+
+    fn output(&self, captured: &'a H) -> Option<Self::Value> {
+        Some(captured)
+    }
+}
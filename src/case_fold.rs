@@ -0,0 +1,118 @@
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// Simple case folding of the char
+///
+/// Two chars are equal ignoring case if their folds are equal.
+/// The fold is always a single char, so the folding never changes
+/// the number of chars, but can change their length in bytes (e.g. `'K'` of Kelvin sign and `'k'`).
+///
+pub fn fold(ch: char) -> char {
+    match ch {
+        // Turkish dotless i has no simple folding
+        'ı' => return ch,
+        '\u{1FD3}' => return '\u{0390}',
+        '\u{1FE3}' => return '\u{03B0}',
+        '\u{FB05}' => return '\u{FB06}',
+        _ if ch.is_ascii() => return ch.to_ascii_lowercase(),
+        _ => {},
+    }
+
+    // The round trip through the upper case joins chars like 'ς' and 'σ', 'ſ' and 's'
+    match single(ch.to_uppercase()) {
+        Some(upper) => single(upper.to_lowercase()).unwrap_or(upper),
+        None => single(ch.to_lowercase()).unwrap_or(ch),
+    }
+}
+
+fn single(mut chars: impl Iterator<Item = char>) -> Option<char> {
+    match (chars.next(), chars.next()) {
+        (Some(ch), None) => Some(ch),
+        _ => None,
+    }
+}
+
+/// Checks chars are equal ignoring case
+pub fn eq_nocase(a: char, b: char) -> bool {
+    a == b || fold(a) == fold(b)
+}
+
+/// Calls `f` with every char equal to the `ch` ignoring case, including the `ch` itself
+///
+/// Stops as soon as `f` returns `true`.
+///
+pub fn any_case(ch: char, mut f: impl FnMut(char) -> bool) -> bool {
+    if f(ch) {
+        return true;
+    }
+
+    let folded = fold(ch);
+    if folded != ch && f(folded) {
+        return true;
+    }
+
+    match unfolds().get(&folded) {
+        Some(chars) => chars.iter().any(|&other| other != ch && f(other)),
+        None => false,
+    }
+}
+
+/// Chars which fold to the key, except the key itself
+fn unfolds() -> &'static HashMap<char, Vec<char>> {
+    static UNFOLDS: OnceLock<HashMap<char, Vec<char>>> = OnceLock::new();
+
+    UNFOLDS.get_or_init(|| {
+        let mut unfolds: HashMap<char, Vec<char>> = HashMap::new();
+
+        for ch in (0..=char::MAX as u32).filter_map(char::from_u32) {
+            let folded = fold(ch);
+            if folded != ch {
+                unfolds.entry(folded).or_default().push(ch);
+            }
+        }
+
+        unfolds
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fold_chars() {
+        assert_eq!(fold('A'), 'a');
+        assert_eq!(fold('a'), 'a');
+        assert_eq!(fold('Ж'), 'ж');
+        assert_eq!(fold('ς'), 'σ');
+        assert_eq!(fold('ſ'), 's');
+        assert_eq!(fold('\u{212A}'), 'k');
+        assert_eq!(fold('ẞ'), 'ß');
+        assert_eq!(fold('ß'), 'ß');
+        assert_eq!(fold('ı'), 'ı');
+        assert_eq!(fold('İ'), 'İ');
+        assert_eq!(fold('1'), '1');
+    }
+
+    #[test]
+    fn all_cases() {
+        let mut chars = Vec::new();
+        any_case('k', |ch| {
+            chars.push(ch);
+            false
+        });
+
+        chars.sort_unstable();
+        assert_eq!(chars, ['K', 'k', '\u{212A}']);
+
+        let mut sigmas = Vec::new();
+        any_case('Σ', |ch| {
+            sigmas.push(ch);
+            false
+        });
+
+        sigmas.sort_unstable();
+        assert_eq!(sigmas, ['Σ', 'ς', 'σ']);
+    }
+}
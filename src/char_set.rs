@@ -0,0 +1,151 @@
+
+use crate::scan_term::ScanTerm;
+use crate::case_fold::any_case;
+
+/// Set of chars which matches a single char
+///
+/// ASCII chars are looked up in a bitmap, others in a sorted list.
+///
+#[derive(Clone, Debug)]
+pub struct CharSet {
+    ascii: [u64; 2],
+    others: Vec<char>,
+    negated: bool,
+}
+
+impl CharSet {
+    pub fn new(chars: impl IntoIterator<Item = char>) -> CharSet {
+        let mut ascii = [0; 2];
+        let mut others = Vec::new();
+
+        for ch in chars {
+            if ch.is_ascii() {
+                ascii[ch as usize / 64] |= 1 << (ch as usize % 64);
+            } else {
+                others.push(ch);
+            }
+        }
+
+        others.sort_unstable();
+        others.dedup();
+
+        CharSet {
+            ascii,
+            others,
+            negated: false,
+        }
+    }
+
+    /// Set of all chars except the given ones
+    pub fn negated(chars: impl IntoIterator<Item = char>) -> CharSet {
+        CharSet {
+            negated: true,
+            ..CharSet::new(chars)
+        }
+    }
+
+    /// Checks the char is in the set
+    pub fn contains(&self, ch: char) -> bool {
+        self.has(ch) != self.negated
+    }
+
+    fn has(&self, ch: char) -> bool {
+        if ch.is_ascii() {
+            self.ascii[ch as usize / 64] & 1 << (ch as usize % 64) != 0
+        } else {
+            self.others.binary_search(&ch).is_ok()
+        }
+    }
+}
+
+impl<const N: usize> From<[char; N]> for CharSet {
+    fn from(chars: [char; N]) -> CharSet {
+        CharSet::new(chars)
+    }
+}
+
+impl From<&[char]> for CharSet {
+    fn from(chars: &[char]) -> CharSet {
+        CharSet::new(chars.iter().copied())
+    }
+}
+
+impl ScanTerm for CharSet {
+    fn scan_term(&self, text: &str) -> Option<usize> {
+        match text.chars().next() {
+            Some(ch) if self.contains(ch) => Some(ch.len_utf8()),
+            _ => None,
+        }
+    }
+
+    fn scan_term_nocase(&self, text: &str) -> Option<usize> {
+        match text.chars().next() {
+            Some(ch) if any_case(ch, |other| self.has(other)) != self.negated => Some(ch.len_utf8()),
+            _ => None,
+        }
+    }
+
+    fn term_width(&self) -> Option<usize> {
+        Some(1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn char_set() {
+        let vowels = CharSet::new("aeiouё".chars());
+        assert!(vowels.contains('a'));
+        assert!(vowels.contains('u'));
+        assert!(vowels.contains('ё'));
+        assert!(!vowels.contains('b'));
+        assert!(!vowels.contains('я'));
+        assert!(!vowels.contains('\u{7F}'));
+
+        assert_eq!(vowels.scan_term("ёж"), Some(2));
+        assert_eq!(vowels.scan_term("x"), None);
+        assert_eq!(vowels.scan_term(""), None);
+
+        let edges = CharSet::new(vec!['\u{0}', '?', '@', '\u{7F}']);
+        assert!(edges.contains('\u{0}'));
+        assert!(edges.contains('?'));
+        assert!(edges.contains('@'));
+        assert!(edges.contains('\u{7F}'));
+        assert!(!edges.contains('A'));
+    }
+
+    #[test]
+    fn char_set_from_chars() {
+        let brackets = CharSet::from(['[', '(', '{', '«']);
+        assert!(brackets.contains('('));
+        assert!(brackets.contains('«'));
+        assert!(!brackets.contains(')'));
+
+        let unsorted: &[char] = &['я', 'a', 'ж'];
+        let letters = CharSet::from(unsorted);
+        assert!(letters.contains('ж'));
+        assert!(!letters.contains('b'));
+    }
+
+    #[test]
+    fn negated_char_set() {
+        let separators = CharSet::negated(",;".chars());
+        assert_eq!(separators.scan_term("a"), Some(1));
+        assert_eq!(separators.scan_term("я"), Some(2));
+        assert_eq!(separators.scan_term(","), None);
+        assert_eq!(separators.scan_term(""), None);
+    }
+
+    #[test]
+    fn char_set_nocase() {
+        let vowels = CharSet::new("aeiou".chars());
+        assert_eq!(vowels.scan_term_nocase("E"), Some(1));
+        assert_eq!(vowels.scan_term_nocase("B"), None);
+
+        let consonants = CharSet::negated("aeiou".chars());
+        assert_eq!(consonants.scan_term_nocase("E"), None);
+        assert_eq!(consonants.scan_term_nocase("B"), Some(1));
+    }
+}
@@ -0,0 +1,547 @@
+
+use std::collections::HashMap;
+use std::fmt;
+use std::mem::size_of;
+use crate::case_fold::eq_nocase;
+use crate::patterns::Anchor;
+use super::{Compiled, Inst};
+
+/// Default memory limit (in bytes) of the cache of DFA states
+pub const DEFAULT_MEMORY_LIMIT: usize = 2 * 1024 * 1024;
+
+/// How many times the cache can be cleared during one search before falling back to the Pike VM
+const MAX_CLEARS: usize = 3;
+
+/// Transition is not computed yet
+const UNKNOWN: u32 = u32::MAX;
+
+/// Flag of a transition: a match ends before the char
+const MATCH: u32 = 1 << 31;
+
+/// Flag of a transition: the match ending before the char is empty
+const EMPTY: u32 = 1 << 30;
+
+const STATE_ID: u32 = EMPTY - 1;
+
+/// Kind of the char next to a position, it's all anchors need to know about the char
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+enum Look {
+    Edge,
+    Newline,
+    Return,
+    Word,
+    Other,
+}
+
+impl Look {
+    fn of(ch: Option<char>) -> Look {
+        match ch {
+            None => Look::Edge,
+            Some('\n') => Look::Newline,
+            Some('\r') => Look::Return,
+            Some(ch) if Anchor::WordBoundary.is_at("", ch.encode_utf8(&mut [0; 4]) as &str) => Look::Word,
+            Some(_) => Look::Other,
+        }
+    }
+
+    /// Text with a char of the kind
+    fn sample(self) -> &'static str {
+        match self {
+            Look::Edge => "",
+            Look::Newline => "\n",
+            Look::Return => "\r",
+            Look::Word => "a",
+            Look::Other => " ",
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum Mode {
+    /// Stops at the first match anywhere in the text
+    IsMatch,
+
+    /// Matches the whole text
+    Full,
+
+    /// Finds the end of the preferred leftmost match
+    Leftmost,
+}
+
+/// State of the DFA, a set of threads of the NFA
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct State {
+    /// Instructions the threads continue from in the order of their priority
+    pcs: Box<[usize]>,
+    prev: Look,
+
+    /// A new thread starts at every position
+    seeding: bool,
+}
+
+impl State {
+    fn is_dead(&self) -> bool {
+        self.pcs.is_empty() && !self.seeding
+    }
+
+    /// Approximate number of bytes the state takes in the cache
+    fn memory(&self) -> usize {
+        2 * (size_of::<State>() + self.pcs.len() * size_of::<usize>())
+            + size_of::<[u32; 128]>()
+            + size_of::<u32>()
+    }
+}
+
+/// Search gave up as the cache is cleared too often
+struct GaveUp;
+
+/// States built so far and transitions between them
+struct Dfa {
+    mode: Mode,
+    states: Vec<State>,
+    ids: HashMap<State, u32>,
+
+    /// Transitions over ASCII chars of every state
+    ascii: Vec<[u32; 128]>,
+    unicode: HashMap<(u32, char), u32>,
+
+    /// Flags of the match at the end of the text of every state
+    ends: Vec<u32>,
+    memory: usize,
+    clears: usize,
+}
+
+impl Dfa {
+    fn new(mode: Mode) -> Dfa {
+        Dfa {
+            mode,
+            states: Vec::new(),
+            ids: HashMap::new(),
+            ascii: Vec::new(),
+            unicode: HashMap::new(),
+            ends: Vec::new(),
+            memory: 0,
+            clears: 0,
+        }
+    }
+
+    fn clear(&mut self) {
+        self.states.clear();
+        self.ids.clear();
+        self.ascii.clear();
+        self.unicode.clear();
+        self.ends.clear();
+        self.memory = 0;
+        self.clears += 1;
+    }
+
+    /// Returns the id of the state adding it to the cache if it's new
+    fn add(&mut self, state: State, limit: usize) -> Result<u32, GaveUp> {
+        if let Some(&id) = self.ids.get(&state) {
+            return Ok(id);
+        }
+
+        let memory = state.memory();
+        if self.memory + memory > limit {
+            if self.clears >= MAX_CLEARS {
+                return Err(GaveUp);
+            }
+            self.clear();
+        }
+
+        let id = self.states.len() as u32;
+        if id >= EMPTY {
+            return Err(GaveUp);
+        }
+
+        self.memory += memory;
+        self.states.push(state.clone());
+        self.ids.insert(state, id);
+        self.ascii.push([UNKNOWN; 128]);
+        self.ends.push(UNKNOWN);
+        Ok(id)
+    }
+
+    /// Follows empty transitions of the threads before the `next` char
+    ///
+    /// Returns instructions which consume chars in the order of their priority
+    /// and flags of the match ending at the position.
+    ///
+    fn closure(&self, insts: &[Inst<'_>], state: &State, next: Option<char>) -> (Vec<usize>, u32) {
+        let mut closure = Closure {
+            insts,
+            mode: self.mode,
+            prev: state.prev.sample(),
+            next: Look::of(next).sample(),
+            at_end: next.is_none(),
+            added: vec![false; insts.len()],
+            stack: Vec::new(),
+            leaves: Vec::new(),
+            matched: 0,
+        };
+
+        for &pc in state.pcs.iter() {
+            if closure.add(pc) {
+                return (closure.leaves, closure.matched);
+            }
+        }
+
+        if state.seeding && closure.add(0) {
+            closure.matched |= EMPTY;
+        }
+
+        (closure.leaves, closure.matched)
+    }
+
+    /// Returns the transition from the state over the char
+    fn next(&mut self, insts: &[Inst<'_>], asserts: bool, id: u32, ch: char, limit: usize) -> Result<u32, GaveUp> {
+        let cached = if ch.is_ascii() {
+            self.ascii[id as usize][ch as usize]
+        } else {
+            self.unicode.get(&(id, ch)).copied().unwrap_or(UNKNOWN)
+        };
+
+        if cached != UNKNOWN {
+            return Ok(cached);
+        }
+
+        let state = self.states[id as usize].clone();
+        let (leaves, matched) = self.closure(insts, &state, Some(ch));
+        let mut buf = [0; 4];
+        let encoded: &str = ch.encode_utf8(&mut buf);
+
+        let mut pcs = Vec::new();
+        let mut added = vec![false; insts.len() + 1];
+        for pc in leaves {
+            let step = match insts[pc] {
+                Inst::Char(expected) => ch == expected,
+                Inst::CharNoCase(expected) => eq_nocase(ch, expected),
+                Inst::Class(term) => term.scan_term(encoded) == Some(encoded.len()),
+                Inst::ClassNoCase(term) => term.scan_term_nocase(encoded) == Some(encoded.len()),
+                _ => false,
+            };
+
+            if step && !added[pc + 1] {
+                added[pc + 1] = true;
+                pcs.push(pc + 1);
+            }
+        }
+
+        let next = State {
+            pcs: pcs.into_boxed_slice(),
+            prev: if asserts { Look::of(Some(ch)) } else { Look::Edge },
+            seeding: state.seeding && !(self.mode == Mode::Leftmost && matched & MATCH != 0),
+        };
+
+        let clears = self.clears;
+        let next_id = self.add(next, limit)?;
+
+        // The cache is cleared, so the state has a new id
+        let id = if self.clears == clears {
+            id
+        } else {
+            let id = self.add(state, limit)?;
+            if self.clears != clears + 1 {
+                return Err(GaveUp);
+            }
+            id
+        };
+
+        let transition = next_id | matched;
+        let memory = size_of::<((u32, char), u32)>() * 2;
+        if ch.is_ascii() {
+            self.ascii[id as usize][ch as usize] = transition;
+        } else if self.memory + memory <= limit {
+            self.memory += memory;
+            self.unicode.insert((id, ch), transition);
+        }
+
+        Ok(transition)
+    }
+
+    /// Returns flags of the match at the end of the text
+    fn end(&mut self, insts: &[Inst<'_>], id: u32) -> u32 {
+        if self.ends[id as usize] == UNKNOWN {
+            let (_, matched) = self.closure(insts, &self.states[id as usize], None);
+            self.ends[id as usize] = matched;
+        }
+
+        self.ends[id as usize]
+    }
+
+    /// Runs the DFA from the position, returns the end of the match and whether the match is empty
+    fn search(&mut self, insts: &[Inst<'_>], asserts: bool, text: &str, start: usize, limit: usize) -> Result<Option<(usize, bool)>, GaveUp> {
+        self.clears = 0;
+
+        let prev = if asserts { Look::of(text[..start].chars().next_back()) } else { Look::Edge };
+        let anchored = self.mode == Mode::Full;
+        let mut id = self.add(State {
+            pcs: if anchored { vec![0].into_boxed_slice() } else { Box::new([]) },
+            prev,
+            seeding: !anchored,
+        }, limit)?;
+
+        let mut found = None;
+
+        for (i, ch) in text[start..].char_indices() {
+            let transition = self.next(insts, asserts, id, ch, limit)?;
+
+            if transition & MATCH != 0 && self.mode != Mode::Full {
+                found = Some((start + i, transition & EMPTY != 0));
+                if self.mode == Mode::IsMatch {
+                    return Ok(found);
+                }
+            }
+
+            id = transition & STATE_ID;
+            if self.states[id as usize].is_dead() {
+                return Ok(found);
+            }
+        }
+
+        let matched = self.end(insts, id);
+        if matched & MATCH != 0 {
+            found = Some((text.len(), matched & EMPTY != 0));
+        }
+
+        Ok(found)
+    }
+}
+
+/// Empty transitions from threads of a state
+struct Closure<'i, 'p> {
+    insts: &'i [Inst<'p>],
+    mode: Mode,
+    prev: &'static str,
+    next: &'static str,
+    at_end: bool,
+    added: Vec<bool>,
+    stack: Vec<usize>,
+    leaves: Vec<usize>,
+    matched: u32,
+}
+
+impl Closure<'_, '_> {
+    /// Adds the thread, returns true if threads of lower priority must be dropped
+    fn add(&mut self, pc: usize) -> bool {
+        self.stack.push(pc);
+
+        while let Some(pc) = self.stack.pop() {
+            if self.added[pc] {
+                continue;
+            }
+            self.added[pc] = true;
+
+            match self.insts[pc] {
+                Inst::Jump(target) => self.stack.push(target),
+                Inst::Split(first, second) => {
+                    self.stack.push(second);
+                    self.stack.push(first);
+                },
+                Inst::Save(_) => self.stack.push(pc + 1),
+                Inst::Assert(anchor) => {
+                    if anchor.is_at(self.prev, self.next) {
+                        self.stack.push(pc + 1);
+                    }
+                },
+                Inst::Fail => {},
+                Inst::Match => {
+                    match self.mode {
+                        Mode::Full if !self.at_end => {},
+                        Mode::Leftmost => {
+                            self.matched = MATCH;
+                            self.stack.clear();
+                            return true;
+                        },
+                        _ => self.matched = MATCH,
+                    }
+                },
+                _ => self.leaves.push(pc),
+            }
+        }
+
+        false
+    }
+}
+
+/// Compiled pattern which is run by a lazily built DFA
+///
+/// The DFA doesn't track groups, so it only checks the pattern matches and counts matches,
+/// which is much faster than the Pike VM when many texts are checked with the same pattern.
+/// States of the DFA are built on demand and cached, the cache is cleared when it grows
+/// over the memory limit. If the cache is cleared too often during one search,
+/// the search falls back to the Pike VM.
+///
+/// Single-char terms (closures too) are evaluated once per char and state
+/// and the result is cached, so they must return the same result for the same char.
+///
+/// # Examples
+/// ```
+/// # use rep::{Pattern, ScanTerm};
+/// let level = Pattern("ERROR") | "WARN";
+/// let mut dfa = level.lazy_dfa().unwrap();
+///
+/// let log = ["INFO start", "WARN disk", "ERROR disk", "INFO stop"];
+/// let problems = log.iter().filter(|line| dfa.is_match(line)).count();
+/// assert_eq!(problems, 2);
+///
+/// assert_eq!(dfa.count("WARN, WARN, ERROR"), 3);
+/// ```
+///
+pub struct LazyDfa<'p> {
+    compiled: Compiled<'p>,
+
+    /// The program has anchors, so states depend on the previous char
+    asserts: bool,
+    memory_limit: usize,
+    is_match: Dfa,
+    full: Dfa,
+    leftmost: Dfa,
+}
+
+impl<'p> LazyDfa<'p> {
+    pub fn new(compiled: Compiled<'p>) -> LazyDfa<'p> {
+        LazyDfa::with_memory_limit(compiled, DEFAULT_MEMORY_LIMIT)
+    }
+
+    /// Keeps at most `memory_limit` bytes of cached states (for each kind of search)
+    pub fn with_memory_limit(compiled: Compiled<'p>, memory_limit: usize) -> LazyDfa<'p> {
+        LazyDfa {
+            asserts: compiled.insts.iter().any(|inst| matches!(inst, Inst::Assert(_))),
+            compiled,
+            memory_limit,
+            is_match: Dfa::new(Mode::IsMatch),
+            full: Dfa::new(Mode::Full),
+            leftmost: Dfa::new(Mode::Leftmost),
+        }
+    }
+
+    /// Approximate number of bytes taken by cached states
+    pub fn memory_usage(&self) -> usize {
+        self.is_match.memory + self.full.memory + self.leftmost.memory
+    }
+
+    /// Checks the whole text matches the pattern
+    pub fn test(&mut self, text: &str) -> bool {
+        match self.full.search(&self.compiled.insts, self.asserts, text, 0, self.memory_limit) {
+            Ok(found) => found.is_some(),
+            Err(GaveUp) => self.compiled.test(text),
+        }
+    }
+
+    /// Checks the pattern matches anywhere in the text
+    pub fn is_match(&mut self, text: &str) -> bool {
+        match self.is_match.search(&self.compiled.insts, self.asserts, text, 0, self.memory_limit) {
+            Ok(found) => found.is_some(),
+            Err(GaveUp) => self.compiled.is_match(text),
+        }
+    }
+
+    /// Number of successive non-overlapping matches, the same as `Compiled::matches(text).count()`
+    pub fn count(&mut self, text: &str) -> usize {
+        let mut count = 0;
+        let mut pos = 0;
+        let mut skip = false;
+
+        loop {
+            if skip {
+                match text[pos..].chars().next() {
+                    Some(ch) => pos += ch.len_utf8(),
+                    None => return count,
+                }
+            }
+
+            let found = match self.leftmost.search(&self.compiled.insts, self.asserts, text, pos, self.memory_limit) {
+                Ok(found) => found,
+                Err(GaveUp) => self.compiled.find_at(text, pos).map(|m| (m.span().end, m.span().is_empty())),
+            };
+
+            match found {
+                Some((end, empty)) => {
+                    count += 1;
+                    pos = end;
+                    skip = empty;
+                },
+                None => return count,
+            }
+        }
+    }
+}
+
+impl fmt::Debug for LazyDfa<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LazyDfa")
+            .field("compiled", &self.compiled)
+            .field("memory_limit", &self.memory_limit)
+            .field("memory_usage", &self.memory_usage())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Pattern, cap, nocase, one_of, start, end, line_start, line_end, word_boundary};
+    use super::LazyDfa;
+
+    const TEXTS: [&str; 9] = ["", "a", "ab ab", "abc\nab\r\n", "xx_ab ba", "фыв ab", "aaaa", "b a", "\r\na"];
+
+    fn same_as_pike_vm(dfa: &mut LazyDfa<'_>) {
+        for text in TEXTS {
+            assert_eq!(dfa.is_match(text), dfa.compiled.is_match(text), "is_match {:?}", text);
+            assert_eq!(dfa.test(text), dfa.compiled.test(text), "test {:?}", text);
+            assert_eq!(dfa.count(text), dfa.compiled.matches(text).count(), "count {:?}", text);
+        }
+    }
+
+    #[test]
+    fn same_results_as_pike_vm() {
+        let letters = Pattern(char::is_alphabetic) * (1..);
+        same_as_pike_vm(&mut letters.lazy_dfa().unwrap());
+
+        let alternation = Pattern("ab") | 'a' | "";
+        same_as_pike_vm(&mut alternation.lazy_dfa().unwrap());
+
+        let lazy = (Pattern('a') * (1..=3)).lazy() & (Pattern('b') * ..);
+        same_as_pike_vm(&mut lazy.lazy_dfa().unwrap());
+
+        let groups = (cap(Pattern('a') | 'b') * 2) & ' ';
+        same_as_pike_vm(&mut groups.lazy_dfa().unwrap());
+
+        let case = nocase(Pattern("AB") | one_of("Ф"));
+        same_as_pike_vm(&mut case.lazy_dfa().unwrap());
+    }
+
+    #[test]
+    fn anchors() {
+        let word = word_boundary() & (Pattern(char::is_alphabetic) * (1..)) & word_boundary();
+        same_as_pike_vm(&mut word.lazy_dfa().unwrap());
+
+        let line = line_start() & (Pattern('a') * ..) & line_end();
+        same_as_pike_vm(&mut line.lazy_dfa().unwrap());
+
+        let whole = start() & (Pattern('a') | 'b') & end();
+        same_as_pike_vm(&mut whole.lazy_dfa().unwrap());
+
+        let suffix = Pattern("ab") & word_boundary();
+        let mut dfa = suffix.lazy_dfa().unwrap();
+        assert_eq!(dfa.count("ab abc ab_ ab!"), 2);
+    }
+
+    #[test]
+    fn memory_limit() {
+        // Needs a state for every position in the last 20 chars
+        let suffix = (Pattern(|_: char| true) * ..) & 'a' & (Pattern(|_: char| true) * 20);
+        let compiled = suffix.compile().unwrap();
+        let text = "ab".repeat(50);
+
+        let mut limited = LazyDfa::with_memory_limit(suffix.compile().unwrap(), 4096);
+        assert!(limited.is_match(&text));
+        assert!(!limited.is_match(&"b".repeat(100)));
+        assert_eq!(limited.count(&text), compiled.matches(&text).count());
+        assert!(limited.memory_usage() <= 3 * 4096);
+
+        let mut unlimited = suffix.lazy_dfa().unwrap();
+        assert!(unlimited.is_match(&text));
+        assert!(unlimited.memory_usage() > 4096);
+    }
+}
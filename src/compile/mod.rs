@@ -0,0 +1,362 @@
+
+mod pike_vm;
+mod lazy_dfa;
+mod push_vm;
+
+use std::fmt;
+use std::error::Error;
+use std::ops::{Bound, Range, RangeBounds, RangeInclusive};
+use crate::scan_term::ScanTerm;
+use crate::char_set::CharSet;
+use crate::unicode::{GeneralCategory, Script, Property};
+use crate::patterns::{
+    Pattern,
+    AndPattern,
+    OrPattern,
+    ManyPattern,
+    RangePattern,
+    LazyPattern,
+    Cap,
+    NoCase,
+    Anchor,
+};
+
+pub use pike_vm::{Compiled, CompiledMatch, CompiledMatches};
+pub use lazy_dfa::LazyDfa;
+pub use push_vm::CompiledPushMatcher;
+
+/// Instruction of the compiled program
+#[derive(Copy, Clone)]
+pub(crate) enum Inst<'p> {
+    Char(char),
+    CharNoCase(char),
+
+    /// Term which matches exactly one char
+    Class(&'p dyn ScanTerm),
+    ClassNoCase(&'p dyn ScanTerm),
+    Assert(Anchor),
+
+    /// Continues at both targets, the first one is preferred
+    Split(usize, usize),
+    Jump(usize),
+
+    /// Saves the position to the capture slot
+    Save(usize),
+    Match,
+    Fail,
+}
+
+/// Default maximum number of instructions of a compiled program
+pub(crate) const DEFAULT_SIZE_LIMIT: usize = 1 << 20;
+
+/// Error of compiling a pattern
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CompileError {
+    /// The program needs more instructions than the limit,
+    /// e.g. a bounded repetition with a large count is unrolled
+    TooBig { limit: usize },
+}
+
+impl fmt::Display for CompileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CompileError::TooBig { limit } => write!(f, "compiled program exceeds the limit of {} instructions", limit),
+        }
+    }
+}
+
+impl Error for CompileError {}
+
+/// Pattern which can be compiled into an NFA program
+///
+/// Implemented by sequences, alternations, repetitions, groups, `nocase` and anchors
+/// of compilable patterns, by string literals and by terms which match exactly one char.
+/// Patterns which need backtracking (backreferences, lookarounds, atomic groups, ...)
+/// are not compilable.
+///
+pub trait Compile {
+    fn compile<'p>(&'p self, compiler: &mut Compiler<'p>);
+}
+
+/// Builder of the program of a pattern
+pub struct Compiler<'p> {
+    insts: Vec<Inst<'p>>,
+    names: Vec<Option<&'static str>>,
+    next_group: usize,
+    nocase: bool,
+    size_limit: usize,
+}
+
+impl<'p> Compiler<'p> {
+    fn new(size_limit: usize) -> Compiler<'p> {
+        Compiler {
+            insts: Vec::new(),
+            names: Vec::new(),
+            next_group: 0,
+            nocase: false,
+            size_limit,
+        }
+    }
+
+    /// Compiles the pattern into the program which captures the whole match to the slots 0 and 1
+    ///
+    /// Fails if the program has more instructions than the limit.
+    ///
+    pub(crate) fn program<S: Compile>(
+        pattern: &'p S,
+        size_limit: usize,
+    ) -> Result<(Vec<Inst<'p>>, Vec<Option<&'static str>>), CompileError> {
+        let mut compiler = Compiler::new(size_limit);
+        compiler.emit(Inst::Save(0));
+        pattern.compile(&mut compiler);
+        compiler.emit(Inst::Save(1));
+        compiler.emit(Inst::Match);
+
+        if compiler.is_too_big() {
+            return Err(CompileError::TooBig { limit: size_limit });
+        }
+        Ok((compiler.insts, compiler.names))
+    }
+
+    /// Checks the program exceeds the limit already, so there is no point to emit more
+    fn is_too_big(&self) -> bool {
+        self.insts.len() > self.size_limit
+    }
+
+    fn emit(&mut self, inst: Inst<'p>) -> usize {
+        self.insts.push(inst);
+        self.insts.len() - 1
+    }
+
+    fn pc(&self) -> usize {
+        self.insts.len()
+    }
+
+    /// Matches the chars of the text one by one
+    pub fn literal(&mut self, text: &str) {
+        for ch in text.chars() {
+            let inst = if self.nocase { Inst::CharNoCase(ch) } else { Inst::Char(ch) };
+            self.emit(inst);
+        }
+    }
+
+    /// Matches one char with the term, the term must never match more or less than one char
+    pub fn class<T: ScanTerm>(&mut self, term: &'p T) {
+        let inst = if self.nocase { Inst::ClassNoCase(term) } else { Inst::Class(term) };
+        self.emit(inst);
+    }
+
+    pub fn anchor(&mut self, anchor: Anchor) {
+        self.emit(Inst::Assert(anchor));
+    }
+
+    /// Alternation which prefers the first branch
+    pub fn alternate(&mut self, first: impl FnOnce(&mut Self), second: impl FnOnce(&mut Self)) {
+        let split = self.emit(Inst::Fail);
+        first(self);
+        let jump = self.emit(Inst::Fail);
+        let second_pc = self.pc();
+        second(self);
+
+        self.insts[split] = Inst::Split(split + 1, second_pc);
+        self.insts[jump] = Inst::Jump(self.pc());
+    }
+
+    /// Repetition from `min` to `max` times (unbounded if there is no `max`)
+    ///
+    /// Groups of every copy of the body share slots, so the last iteration is captured.
+    /// Bounded repetitions are unrolled, the unrolling stops as soon as the program exceeds the limit.
+    ///
+    pub fn repeat(&mut self, body: impl Fn(&mut Self), min: u32, max: Option<u32>, greedy: bool) {
+        let mark = self.next_group;
+        let copy = |compiler: &mut Self| {
+            compiler.next_group = mark;
+            body(compiler);
+        };
+
+        if max.is_some_and(|max| max < min) {
+            self.emit(Inst::Fail);
+        }
+
+        for _ in 0..min {
+            if self.is_too_big() {
+                return;
+            }
+            copy(self);
+        }
+
+        let split = |from: usize, to: usize| if greedy { Inst::Split(from + 1, to) } else { Inst::Split(to, from + 1) };
+
+        match max {
+            None => {
+                let start = self.emit(Inst::Fail);
+                copy(self);
+                self.emit(Inst::Jump(start));
+                self.insts[start] = split(start, self.pc());
+            },
+            Some(max) => {
+                let mut starts = Vec::new();
+                for _ in min..max {
+                    if self.is_too_big() {
+                        return;
+                    }
+                    starts.push(self.emit(Inst::Fail));
+                    copy(self);
+                }
+
+                let end = self.pc();
+                for start in starts {
+                    self.insts[start] = split(start, end);
+                }
+            },
+        }
+
+        // Groups are numbered even if the body is never matched
+        if self.next_group == mark {
+            let pc = self.pc();
+            copy(self);
+            self.insts.truncate(pc);
+        }
+    }
+
+    /// Capture group, returns the number of it (the whole match is the group 0)
+    pub fn group(&mut self, name: Option<&'static str>, body: impl FnOnce(&mut Self)) -> usize {
+        self.next_group += 1;
+        let group = self.next_group;
+        if group > self.names.len() {
+            self.names.push(name);
+        }
+
+        self.emit(Inst::Save(group * 2));
+        body(self);
+        self.emit(Inst::Save(group * 2 + 1));
+        group
+    }
+
+    /// Compiles the body ignoring case
+    pub fn nocase(&mut self, body: impl FnOnce(&mut Self)) {
+        let outer = self.nocase;
+        self.nocase = true;
+        body(self);
+        self.nocase = outer;
+    }
+}
+
+fn bounds<R: RangeBounds<u32>>(range: &R) -> (u32, Option<u32>) {
+    let min = match range.start_bound() {
+        Bound::Included(&min) => min,
+        Bound::Excluded(&min) => min + 1,
+        Bound::Unbounded => 0,
+    };
+
+    let max = match range.end_bound() {
+        Bound::Included(&max) => Some(max),
+        Bound::Excluded(&0) => return (1, Some(0)),
+        Bound::Excluded(&max) => Some(max - 1),
+        Bound::Unbounded => None,
+    };
+
+    (min, max)
+}
+
+impl<S: Compile> Compile for Pattern<S> {
+    fn compile<'p>(&'p self, compiler: &mut Compiler<'p>) {
+        self.0.compile(compiler);
+    }
+}
+
+impl<A: Compile, B: Compile> Compile for AndPattern<A, B> {
+    fn compile<'p>(&'p self, compiler: &mut Compiler<'p>) {
+        self.0.compile(compiler);
+        self.1.compile(compiler);
+    }
+}
+
+impl<A: Compile, B: Compile> Compile for OrPattern<A, B> {
+    fn compile<'p>(&'p self, compiler: &mut Compiler<'p>) {
+        compiler.alternate(|c| self.0.compile(c), |c| self.1.compile(c));
+    }
+}
+
+impl<S: Compile> Compile for ManyPattern<S> {
+    fn compile<'p>(&'p self, compiler: &mut Compiler<'p>) {
+        compiler.repeat(|c| self.0.compile(c), self.1, Some(self.1), true);
+    }
+}
+
+impl<S: Compile, R: RangeBounds<u32>> Compile for RangePattern<S, R> {
+    fn compile<'p>(&'p self, compiler: &mut Compiler<'p>) {
+        let (min, max) = bounds(&self.1);
+        compiler.repeat(|c| self.0.compile(c), min, max, true);
+    }
+}
+
+impl<S: Compile, R: RangeBounds<u32>> Compile for LazyPattern<S, R> {
+    fn compile<'p>(&'p self, compiler: &mut Compiler<'p>) {
+        let (min, max) = bounds(&self.1);
+        compiler.repeat(|c| self.0.compile(c), min, max, false);
+    }
+}
+
+impl<S: Compile> Compile for Cap<S> {
+    fn compile<'p>(&'p self, compiler: &mut Compiler<'p>) {
+        compiler.group(self.2, |c| self.0.compile(c));
+    }
+}
+
+impl<S: Compile> Compile for NoCase<S> {
+    fn compile<'p>(&'p self, compiler: &mut Compiler<'p>) {
+        compiler.nocase(|c| self.0.compile(c));
+    }
+}
+
+impl Compile for Anchor {
+    fn compile<'p>(&'p self, compiler: &mut Compiler<'p>) {
+        compiler.anchor(*self);
+    }
+}
+
+impl Compile for &str {
+    fn compile<'p>(&'p self, compiler: &mut Compiler<'p>) {
+        compiler.literal(self);
+    }
+}
+
+impl Compile for String {
+    fn compile<'p>(&'p self, compiler: &mut Compiler<'p>) {
+        compiler.literal(self);
+    }
+}
+
+impl Compile for char {
+    fn compile<'p>(&'p self, compiler: &mut Compiler<'p>) {
+        compiler.literal(self.encode_utf8(&mut [0; 4]));
+    }
+}
+
+/// Terms which match exactly one char
+macro_rules! compile_class {
+    ($($term:ty),*) => {
+        $(
+            impl Compile for $term {
+                fn compile<'p>(&'p self, compiler: &mut Compiler<'p>) {
+                    compiler.class(self);
+                }
+            }
+        )*
+    };
+}
+
+compile_class!(Range<char>, RangeInclusive<char>, &[char], CharSet, GeneralCategory, Script, Property);
+
+impl<const N: usize> Compile for [char; N] {
+    fn compile<'p>(&'p self, compiler: &mut Compiler<'p>) {
+        compiler.class(self);
+    }
+}
+
+impl<F: Fn(char) -> bool> Compile for F {
+    fn compile<'p>(&'p self, compiler: &mut Compiler<'p>) {
+        compiler.class(self);
+    }
+}
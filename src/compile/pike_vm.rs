@@ -0,0 +1,547 @@
+
+use std::cell::Cell;
+use std::fmt;
+use crate::captures::Span;
+use crate::case_fold::eq_nocase;
+use super::{Compile, CompileError, Compiler, CompiledPushMatcher, Inst, DEFAULT_SIZE_LIMIT};
+
+/// Step of following empty transitions
+enum Frame {
+    Explore(usize),
+
+    /// Restores the slot saved by a thread when all threads following it are added
+    Restore(usize, Option<usize>),
+}
+
+/// Threads of the VM at one position of the text in the order of their priority
+pub(super) struct Threads {
+    pub(super) pcs: Vec<usize>,
+
+    /// All instructions visited at the position, including empty transitions
+    visited: Vec<usize>,
+    added: Vec<bool>,
+
+    /// Capture slots of the threads indexed by their pc
+    slots: Vec<Option<usize>>,
+    slot_count: usize,
+
+    /// Pending steps of `add_thread`, kept to reuse the allocation
+    stack: Vec<Frame>,
+}
+
+impl Threads {
+    pub(super) fn new(inst_count: usize, slot_count: usize) -> Threads {
+        Threads {
+            pcs: Vec::with_capacity(inst_count),
+            visited: Vec::with_capacity(inst_count),
+            added: vec![false; inst_count],
+            slots: vec![None; inst_count * slot_count],
+            slot_count,
+            stack: Vec::new(),
+        }
+    }
+
+    pub(super) fn slots(&self, pc: usize) -> &[Option<usize>] {
+        &self.slots[pc * self.slot_count..(pc + 1) * self.slot_count]
+    }
+
+    pub(super) fn clear(&mut self) {
+        for &pc in &self.visited {
+            self.added[pc] = false;
+        }
+        self.visited.clear();
+        self.pcs.clear();
+    }
+}
+
+/// Threads and slots of a search, kept between searches to reuse their allocations
+struct Cache {
+    current: Threads,
+    next: Threads,
+    scratch: Vec<Option<usize>>,
+}
+
+/// Pattern compiled into a Thompson NFA and run by a Pike VM
+///
+/// The search takes time linear in the length of the text whatever the pattern is,
+/// unlike backtracking which can take exponential time on nested repetitions.
+/// The preferred match is the same as the one of the backtracking engine:
+/// the leftmost match, then the first alternative and the greedy (or lazy) repetition count.
+///
+/// Groups are numbered in the order they appear in the pattern,
+/// a group which doesn't participate in the match is `None`.
+/// A repeated group captures its last iteration.
+///
+/// The tables of threads are allocated by the first search and reused by the next ones.
+///
+/// # Examples
+/// ```
+/// # use rep::{Pattern, ScanTerm, cap};
+/// let digit = Pattern(|c: char| c.is_ascii_digit());
+/// let time = cap(digit * (1..=2)) & ':' & cap(digit * 2);
+/// let compiled = time.compile().unwrap();
+///
+/// let found = compiled.find("at 9:30 pm").unwrap();
+/// assert_eq!(found.as_str(), "9:30");
+/// assert_eq!(found.group(1), Some("9"));
+/// assert_eq!(found.group(2), Some("30"));
+/// ```
+///
+pub struct Compiled<'p> {
+    pub(super) insts: Vec<Inst<'p>>,
+
+    /// Names of groups, the whole match has no name
+    pub(super) names: Vec<Option<&'static str>>,
+
+    /// Threads of the last search, it's empty while a search runs
+    cache: Cell<Option<Cache>>,
+}
+
+impl<'p> Compiled<'p> {
+    /// Compiles the pattern into a program of at most 2^20 instructions
+    pub fn new<S: Compile>(pattern: &'p S) -> Result<Compiled<'p>, CompileError> {
+        Compiled::with_size_limit(pattern, DEFAULT_SIZE_LIMIT)
+    }
+
+    /// Compiles the pattern into a program of at most `size_limit` instructions
+    ///
+    /// Bounded repetitions are unrolled, so a large count can make the program huge.
+    ///
+    /// # Examples
+    /// ```
+    /// # use rep::{Pattern, Compiled, CompileError};
+    /// let pair = Pattern('a') * 2;
+    /// assert!(Compiled::with_size_limit(&pair, 10).is_ok());
+    ///
+    /// let many = Pattern('a') * 1000;
+    /// assert_eq!(Compiled::with_size_limit(&many, 10).unwrap_err(), CompileError::TooBig { limit: 10 });
+    /// ```
+    ///
+    pub fn with_size_limit<S: Compile>(pattern: &'p S, size_limit: usize) -> Result<Compiled<'p>, CompileError> {
+        let (insts, names) = Compiler::program(pattern, size_limit)?;
+        Ok(Compiled {
+            insts,
+            names,
+            cache: Cell::new(None),
+        })
+    }
+
+    /// Number of groups including the whole match
+    pub fn group_count(&self) -> usize {
+        self.names.len() + 1
+    }
+
+    /// Checks the whole text matches the pattern
+    pub fn test(&self, text: &str) -> bool {
+        self.run(text, 0, true, true).is_some()
+    }
+
+    /// Checks the pattern matches anywhere in the text
+    pub fn is_match(&self, text: &str) -> bool {
+        self.find(text).is_some()
+    }
+
+    /// Matches the pattern at the beginning of the text
+    pub fn match_prefix<'a>(&'a self, text: &'a str) -> Option<CompiledMatch<'a>> {
+        self.run(text, 0, true, false)
+    }
+
+    /// Finds the first match of the pattern in the text
+    pub fn find<'a>(&'a self, text: &'a str) -> Option<CompiledMatch<'a>> {
+        self.find_at(text, 0)
+    }
+
+    /// Finds the first match of the pattern starting at the position (in bytes) or after it
+    ///
+    /// # Panics
+    ///
+    /// Panics if the position is not at a char boundary or is out of the text.
+    ///
+    pub fn find_at<'a>(&'a self, text: &'a str, start: usize) -> Option<CompiledMatch<'a>> {
+        assert!(text.is_char_boundary(start), "position is not at a char boundary");
+        self.run(text, start, false, false)
+    }
+
+    /// Iterator over successive non-overlapping matches
+    ///
+    /// After an empty match the search continues from the next char, like `str::matches`.
+    ///
+    pub fn matches<'a>(&'a self, text: &'a str) -> CompiledMatches<'a, 'p> {
+        CompiledMatches {
+            compiled: self,
+            text,
+            pos: 0,
+            skip: false,
+            done: false,
+        }
+    }
+
+    /// Matcher of the pattern at the beginning of input which arrives in pieces
+    ///
+    /// See `CompiledPushMatcher` for details.
+    ///
+    pub fn push_matcher(&self) -> CompiledPushMatcher<'_, 'p> {
+        CompiledPushMatcher::new(self)
+    }
+
+    /// Runs the VM from the position, `full` match must end at the end of the text
+    fn run<'a>(&'a self, text: &'a str, start: usize, anchored: bool, full: bool) -> Option<CompiledMatch<'a>> {
+        let slot_count = self.group_count() * 2;
+        let Cache { mut current, mut next, mut scratch } = self.cache.take().unwrap_or_else(|| Cache {
+            current: Threads::new(self.insts.len(), slot_count),
+            next: Threads::new(self.insts.len(), slot_count),
+            scratch: vec![None; slot_count],
+        });
+        let mut matched: Option<Vec<Option<usize>>> = None;
+        let mut pos = start;
+
+        loop {
+            // A new thread starting here has the lowest priority
+            if matched.is_none() && (!anchored || pos == start) {
+                scratch.iter_mut().for_each(|slot| *slot = None);
+                self.add_thread(&mut current, 0, &mut scratch, text, pos);
+            }
+
+            // No thread can match and no new thread starts
+            if current.pcs.is_empty() && (anchored || matched.is_some()) {
+                break;
+            }
+
+            let ch = text[pos..].chars().next();
+            let next_pos = pos + ch.map_or(0, char::len_utf8);
+
+            for i in 0..current.pcs.len() {
+                let pc = current.pcs[i];
+                let step = match (self.insts[pc], ch) {
+                    (Inst::Match, _) => {
+                        if !full || pos == text.len() {
+                            // Threads after this one have lower priority
+                            matched = Some(current.slots(pc).to_vec());
+                            break;
+                        }
+                        false
+                    },
+                    (Inst::Char(expected), Some(ch)) => ch == expected,
+                    (Inst::CharNoCase(expected), Some(ch)) => eq_nocase(ch, expected),
+                    (Inst::Class(term), Some(_)) => term.scan_term(&text[pos..next_pos]) == Some(next_pos - pos),
+                    (Inst::ClassNoCase(term), Some(_)) => term.scan_term_nocase(&text[pos..next_pos]) == Some(next_pos - pos),
+                    _ => false,
+                };
+
+                if step {
+                    scratch.copy_from_slice(current.slots(pc));
+                    self.add_thread(&mut next, pc + 1, &mut scratch, text, next_pos);
+                }
+            }
+
+            if ch.is_none() {
+                break;
+            }
+
+            pos = next_pos;
+            std::mem::swap(&mut current, &mut next);
+            next.clear();
+        }
+
+        current.clear();
+        next.clear();
+        self.cache.set(Some(Cache { current, next, scratch }));
+
+        matched.map(|slots| CompiledMatch {
+            text,
+            slots,
+            names: &self.names,
+        })
+    }
+
+    /// Adds the thread following empty transitions in the order of their priority
+    ///
+    /// Transitions are followed with an explicit stack, so long chains of them
+    /// (like an unrolled repetition of an optional pattern) can't overflow the call stack.
+    ///
+    pub(super) fn add_thread(&self, threads: &mut Threads, pc: usize, slots: &mut [Option<usize>], text: &str, pos: usize) {
+        threads.stack.push(Frame::Explore(pc));
+
+        while let Some(frame) = threads.stack.pop() {
+            let pc = match frame {
+                Frame::Explore(pc) => pc,
+                Frame::Restore(slot, old) => {
+                    slots[slot] = old;
+                    continue;
+                },
+            };
+
+            if threads.added[pc] {
+                continue;
+            }
+            threads.added[pc] = true;
+            threads.visited.push(pc);
+
+            match self.insts[pc] {
+                Inst::Jump(target) => threads.stack.push(Frame::Explore(target)),
+                Inst::Split(first, second) => {
+                    // The first target is explored before the second one
+                    threads.stack.push(Frame::Explore(second));
+                    threads.stack.push(Frame::Explore(first));
+                },
+                Inst::Save(slot) => {
+                    threads.stack.push(Frame::Restore(slot, slots[slot]));
+                    slots[slot] = Some(pos);
+                    threads.stack.push(Frame::Explore(pc + 1));
+                },
+                Inst::Assert(anchor) => {
+                    let (before, after) = text.split_at(pos);
+                    if anchor.is_at(before, after) {
+                        threads.stack.push(Frame::Explore(pc + 1));
+                    }
+                },
+                Inst::Fail => {},
+                _ => {
+                    threads.pcs.push(pc);
+                    let start = pc * threads.slot_count;
+                    threads.slots[start..start + threads.slot_count].copy_from_slice(slots);
+                },
+            }
+        }
+    }
+}
+
+impl fmt::Debug for Compiled<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Compiled")
+            .field("insts", &self.insts.len())
+            .field("names", &self.names)
+            .finish()
+    }
+}
+
+/// Match found by a compiled pattern
+#[derive(Clone, Debug)]
+pub struct CompiledMatch<'a> {
+    pub(super) text: &'a str,
+
+    /// Start and end of every group, the whole match is the group 0
+    pub(super) slots: Vec<Option<usize>>,
+    pub(super) names: &'a [Option<&'static str>],
+}
+
+impl<'a> CompiledMatch<'a> {
+    /// Byte offsets of the match in the text
+    pub fn span(&self) -> Span {
+        self.group_span(0).unwrap()
+    }
+
+    pub fn as_str(&self) -> &'a str {
+        &self.text[self.span().range()]
+    }
+
+    /// Byte offsets of the group, the whole match is the group 0
+    pub fn group_span(&self, index: usize) -> Option<Span> {
+        match (*self.slots.get(index * 2)?, *self.slots.get(index * 2 + 1)?) {
+            (Some(start), Some(end)) => Some(Span::new(start, end)),
+            _ => None,
+        }
+    }
+
+    /// Text of the group, the whole match is the group 0
+    pub fn group(&self, index: usize) -> Option<&'a str> {
+        self.group_span(index).map(|span| &self.text[span.range()])
+    }
+
+    /// Text of the first participating group with the name
+    pub fn name(&self, name: &str) -> Option<&'a str> {
+        self.names
+            .iter()
+            .enumerate()
+            .filter(|(_, group)| **group == Some(name))
+            .find_map(|(index, _)| self.group(index + 1))
+    }
+}
+
+/// Iterator over matches of a compiled pattern
+pub struct CompiledMatches<'a, 'p> {
+    compiled: &'a Compiled<'p>,
+    text: &'a str,
+    pos: usize,
+
+    /// The last match is empty and ends at `pos`, so the search continues from the next char
+    skip: bool,
+    done: bool,
+}
+
+impl<'a, 'p> Iterator for CompiledMatches<'a, 'p> {
+    type Item = CompiledMatch<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let mut start = self.pos;
+        if self.skip {
+            match self.text[start..].chars().next() {
+                Some(ch) => start += ch.len_utf8(),
+                None => {
+                    self.done = true;
+                    return None;
+                },
+            }
+        }
+
+        match self.compiled.find_at(self.text, start) {
+            Some(found) => {
+                let span = found.span();
+                self.pos = span.end;
+                self.skip = span.is_empty();
+                Some(found)
+            },
+            None => {
+                self.done = true;
+                None
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Pattern, CompileError, cap, cap_named, nocase, one_of, start, end, word_boundary};
+
+    #[test]
+    fn same_matches_as_backtracking() {
+        let digit = Pattern(|c: char| c.is_ascii_digit());
+        let number = (digit * (1..)) & ((Pattern('.') & (digit * (1..))) * ..=1);
+        let word = Pattern(char::is_alphabetic) * (2..=4);
+        let compiled_number = number.compile().unwrap();
+        let compiled_word = word.compile().unwrap();
+        let texts = ["", "a", "12.5 and 7", "abcdefgh ab", "3.x 4.25.", "фыва 1.0"];
+
+        for text in texts {
+            let expected: Vec<&str> = number.clone().matched_strs(text).collect();
+            let found: Vec<&str> = compiled_number.matches(text).map(|m| m.as_str()).collect();
+            assert_eq!(found, expected, "{:?}", text);
+
+            let expected: Vec<&str> = word.clone().matched_strs(text).collect();
+            let found: Vec<&str> = compiled_word.matches(text).map(|m| m.as_str()).collect();
+            assert_eq!(found, expected, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn priority_of_alternatives_and_repetitions() {
+        let alternation = Pattern("a") | "ab";
+        assert_eq!(alternation.compile().unwrap().find("ab").unwrap().as_str(), "a");
+
+        let greedy = cap(Pattern('a') * (1..)) & (Pattern('a') * ..);
+        let compiled = greedy.compile().unwrap();
+        let found = compiled.find("aaa").unwrap();
+        assert_eq!(found.as_str(), "aaa");
+        assert_eq!(found.group(1), Some("aaa"));
+
+        let lazy = Pattern('<') & cap((Pattern(|c: char| c != '\n') * ..).lazy()) & '>';
+        let compiled = lazy.compile().unwrap();
+        let found = compiled.find("<a> <b>").unwrap();
+        assert_eq!(found.as_str(), "<a>");
+        assert_eq!(found.group(1), Some("a"));
+    }
+
+    #[test]
+    fn groups() {
+        let key = cap_named("key", Pattern(char::is_alphabetic) * (1..));
+        let value = cap_named("value", Pattern(char::is_alphanumeric) * ..);
+        let pair = key & '=' & value;
+        let compiled = pair.compile().unwrap();
+
+        let found = compiled.find(" x=1 ").unwrap();
+        assert_eq!(found.span().range(), 1..4);
+        assert_eq!(found.name("key"), Some("x"));
+        assert_eq!(found.name("value"), Some("1"));
+        assert_eq!(found.group(3), None);
+        assert_eq!(compiled.group_count(), 3);
+
+        let branches = cap(Pattern('a')) | cap(Pattern('b'));
+        let compiled = branches.compile().unwrap();
+        let found = compiled.find("b").unwrap();
+        assert_eq!(found.group(1), None);
+        assert_eq!(found.group(2), Some("b"));
+
+        let repeated = (cap(Pattern(char::is_alphabetic)) * (1..)) & (cap(Pattern('!')) * ..=0);
+        let compiled = repeated.compile().unwrap();
+        assert_eq!(compiled.find("abc").unwrap().group(1), Some("c"));
+        assert_eq!(compiled.group_count(), 3);
+    }
+
+    #[test]
+    fn anchors_and_case() {
+        let word = word_boundary() & "cat" & word_boundary();
+        let found: Vec<usize> = word.compile().unwrap().matches("cat concat cat").map(|m| m.span().start).collect();
+        assert_eq!(found, [0, 11]);
+
+        let whole = start() & (Pattern('a') * (1..)) & end();
+        assert!(whole.compile().unwrap().is_match("aaa"));
+        assert!(!whole.compile().unwrap().is_match("aab"));
+
+        let hello = nocase(Pattern("hello")) & ' ' & nocase(one_of("ÄÖ") * (1..));
+        assert_eq!(hello.compile().unwrap().find("> HeLLo äö!").unwrap().as_str(), "HeLLo äö");
+    }
+
+    #[test]
+    fn prefixes_and_full_matches() {
+        let number = Pattern(|c: char| c.is_ascii_digit()) * (1..);
+        let compiled = number.compile().unwrap();
+
+        assert_eq!(compiled.match_prefix("12ab").unwrap().as_str(), "12");
+        assert!(compiled.match_prefix("ab12").is_none());
+        assert!(compiled.test("123"));
+        assert!(!compiled.test("123a"));
+
+        let alternation = Pattern('a') | "ab";
+        assert!(alternation.compile().unwrap().test("ab"));
+        assert_eq!(compiled.find_at("12 34", 1).unwrap().as_str(), "2");
+    }
+
+    #[test]
+    fn linear_time() {
+        let nested = ((Pattern('a') * ..) * ..) & 'b';
+        let text = "a".repeat(10_000);
+        assert!(!nested.compile().unwrap().is_match(&text));
+
+        let empty: Vec<usize> = (Pattern("") | 'x').compile().unwrap().matches("фx").map(|m| m.span().start).collect();
+        assert_eq!(empty, [0, 2, 3]);
+    }
+
+    #[test]
+    fn threads_are_reused_between_searches() {
+        let pair = cap(Pattern(char::is_alphabetic) * (1..)) & '=' & cap(Pattern(|c: char| c.is_ascii_digit()) * (1..));
+        let compiled = pair.compile().unwrap();
+
+        let found: Vec<(&str, &str)> = compiled.matches("a=1 bc=22 d= e=3")
+            .map(|m| (m.group(1).unwrap(), m.group(2).unwrap()))
+            .collect();
+        assert_eq!(found, [("a", "1"), ("bc", "22"), ("e", "3")]);
+
+        let cache = compiled.cache.take().unwrap();
+        let tables = [cache.current.slots.as_ptr(), cache.next.slots.as_ptr()];
+        compiled.cache.set(Some(cache));
+
+        // A failed search leaves no threads for the next one
+        assert!(!compiled.test("a=1 "));
+        assert!(compiled.test("a=1"));
+        assert_eq!(compiled.find("x=").map(|m| m.span()), None);
+
+        let cache = compiled.cache.take().unwrap();
+        assert!(tables.contains(&cache.current.slots.as_ptr()));
+        assert!(tables.contains(&cache.next.slots.as_ptr()));
+    }
+
+    #[test]
+    fn long_programs() {
+        let optional = (Pattern('a') * ..=1) * (0..=100_000);
+        let compiled = optional.compile().unwrap();
+        assert!(compiled.is_match("b"));
+        assert_eq!(compiled.find("aab").unwrap().as_str(), "aa");
+
+        let huge = (Pattern('a') * 1000) * (0..=1_000_000_000);
+        assert_eq!(huge.compile().unwrap_err(), CompileError::TooBig { limit: 1 << 20 });
+    }
+}
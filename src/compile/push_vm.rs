@@ -0,0 +1,294 @@
+
+use std::mem;
+use crate::push_matcher::Status;
+use crate::case_fold::eq_nocase;
+use super::pike_vm::{Compiled, CompiledMatch, Threads};
+use super::Inst;
+
+/// Push-style matcher of a compiled pattern at the beginning of input which arrives in pieces
+///
+/// Unlike `PushMatcher`, which matches the pattern again over the whole input on every piece,
+/// the threads of the VM are kept between pieces, so every char is processed once
+/// and feeding a long input in small pieces takes linear time.
+/// The status is decided like the one of `PushMatcher`.
+///
+/// Anchors look at the char after the position,
+/// so a pattern with anchors waits for one more char before it steps over a char.
+///
+/// # Examples
+/// ```
+/// # use rep::{Pattern, ScanTerm, Status};
+/// let number = Pattern(|c: char| c.is_ascii_digit()) * (1..);
+/// let compiled = number.compile().unwrap();
+/// let mut matcher = compiled.push_matcher();
+///
+/// assert_eq!(matcher.feed("12"), Status::Incomplete);
+/// assert_eq!(matcher.feed("3;4"), Status::Matched);
+/// assert_eq!(matcher.matched(), Some("123"));
+/// assert_eq!(matcher.rest(), ";4");
+/// ```
+///
+pub struct CompiledPushMatcher<'c, 'p> {
+    compiled: &'c Compiled<'p>,
+    buffer: String,
+    status: Status,
+    finished: bool,
+
+    /// The program has anchors, so the VM needs one char after the position
+    asserts: bool,
+
+    /// Threads at `pos` are added already
+    started: bool,
+    pos: usize,
+    current: Threads,
+    next: Threads,
+    scratch: Vec<Option<usize>>,
+
+    /// Slots of the preferred match found so far
+    matched: Option<Vec<Option<usize>>>,
+}
+
+impl<'c, 'p> CompiledPushMatcher<'c, 'p> {
+    pub fn new(compiled: &'c Compiled<'p>) -> CompiledPushMatcher<'c, 'p> {
+        let slot_count = compiled.group_count() * 2;
+        let inst_count = compiled.insts.len();
+
+        CompiledPushMatcher {
+            compiled,
+            buffer: String::new(),
+            status: Status::Incomplete,
+            finished: false,
+            asserts: compiled.insts.iter().any(|inst| matches!(inst, Inst::Assert(_))),
+            started: false,
+            pos: 0,
+            current: Threads::new(inst_count, slot_count),
+            next: Threads::new(inst_count, slot_count),
+            scratch: vec![None; slot_count],
+            matched: None,
+        }
+    }
+
+    /// Appends the piece to the input and runs the VM over the new chars if the matching is not decided
+    pub fn feed(&mut self, chunk: &str) -> Status {
+        self.buffer.push_str(chunk);
+
+        if self.status == Status::Incomplete {
+            self.status = self.advance();
+        }
+
+        self.status
+    }
+
+    /// Notes no more input follows and decides the matching
+    pub fn finish(&mut self) -> Status {
+        self.finished = true;
+
+        if self.status == Status::Incomplete {
+            self.status = self.advance();
+        }
+
+        self.status
+    }
+
+    pub fn status(&self) -> Status {
+        self.status
+    }
+
+    /// All input received so far
+    pub fn input(&self) -> &str {
+        &self.buffer
+    }
+
+    /// The text matched by the pattern
+    pub fn matched(&self) -> Option<&str> {
+        match self.status {
+            Status::Matched => Some(&self.buffer[..self.len()]),
+            _ => None,
+        }
+    }
+
+    /// The input after the match, or the whole input if the pattern has not matched
+    pub fn rest(&self) -> &str {
+        &self.buffer[self.len()..]
+    }
+
+    /// Groups of the match
+    pub fn captures(&self) -> Option<CompiledMatch<'_>> {
+        match (self.status, &self.matched) {
+            (Status::Matched, Some(slots)) => Some(CompiledMatch {
+                text: &self.buffer,
+                slots: slots.clone(),
+                names: &self.compiled.names,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Drops the matched text and matches the pattern at the beginning of the rest
+    pub fn consume(&mut self) -> Status {
+        let len = self.len();
+        self.buffer.drain(..len);
+
+        self.started = false;
+        self.pos = 0;
+        self.current.clear();
+        self.matched = None;
+        self.status = self.advance();
+        self.status
+    }
+
+    /// Length of the match if the status is `Matched`
+    fn len(&self) -> usize {
+        match (self.status, &self.matched) {
+            (Status::Matched, Some(slots)) => slots[1].unwrap_or(0),
+            _ => 0,
+        }
+    }
+
+    /// Runs the VM while the input is enough to step, returns the new status
+    fn advance(&mut self) -> Status {
+        let text = self.buffer.as_str();
+
+        loop {
+            // Anchors at the start look at the first char
+            if !self.started {
+                if self.asserts && !self.finished && text.is_empty() {
+                    return Status::Incomplete;
+                }
+
+                self.scratch.iter_mut().for_each(|slot| *slot = None);
+                self.compiled.add_thread(&mut self.current, 0, &mut self.scratch, text, 0);
+                self.started = true;
+            }
+
+            // Every thread of higher priority than the match is dead
+            match self.current.pcs.first() {
+                None if self.matched.is_some() => return Status::Matched,
+                None => return Status::Failed,
+                Some(&pc) if matches!(self.compiled.insts[pc], Inst::Match) => {
+                    self.matched = Some(self.current.slots(pc).to_vec());
+                    return Status::Matched;
+                },
+                Some(_) => {},
+            }
+
+            let ch = text[self.pos..].chars().next();
+            let next_pos = self.pos + ch.map_or(0, char::len_utf8);
+            if !self.finished && (ch.is_none() || self.asserts && next_pos == text.len()) {
+                return Status::Incomplete;
+            }
+
+            for i in 0..self.current.pcs.len() {
+                let pc = self.current.pcs[i];
+                let step = match (self.compiled.insts[pc], ch) {
+                    (Inst::Match, _) => {
+                        // Threads after this one have lower priority
+                        self.matched = Some(self.current.slots(pc).to_vec());
+                        break;
+                    },
+                    (Inst::Char(expected), Some(ch)) => ch == expected,
+                    (Inst::CharNoCase(expected), Some(ch)) => eq_nocase(ch, expected),
+                    (Inst::Class(term), Some(_)) => term.scan_term(&text[self.pos..next_pos]) == Some(next_pos - self.pos),
+                    (Inst::ClassNoCase(term), Some(_)) => {
+                        term.scan_term_nocase(&text[self.pos..next_pos]) == Some(next_pos - self.pos)
+                    },
+                    _ => false,
+                };
+
+                if step {
+                    self.scratch.copy_from_slice(self.current.slots(pc));
+                    self.compiled.add_thread(&mut self.next, pc + 1, &mut self.scratch, text, next_pos);
+                }
+            }
+
+            self.pos = next_pos;
+            mem::swap(&mut self.current, &mut self.next);
+            self.next.clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Pattern, Status, cap_named, end, word_boundary};
+
+    #[test]
+    fn same_status_as_push_matcher() {
+        let pieces = ["", "1", "2", "3", ";", "4"];
+        let number = Pattern(|c: char| c.is_ascii_digit()) * (1..);
+        let compiled = number.compile().unwrap();
+
+        let mut matcher = compiled.push_matcher();
+        let mut expected = number.clone().push_matcher();
+        for piece in pieces {
+            assert_eq!(matcher.feed(piece), expected.feed(piece), "{:?}", piece);
+        }
+        assert_eq!(matcher.matched(), Some("123"));
+        assert_eq!(matcher.rest(), ";4");
+
+        let alternation = Pattern("abcd") | "ab";
+        let compiled = alternation.compile().unwrap();
+        let mut matcher = compiled.push_matcher();
+        assert_eq!(matcher.feed("abc"), Status::Incomplete);
+        assert_eq!(matcher.feed("x"), Status::Matched);
+        assert_eq!(matcher.matched(), Some("ab"));
+
+        let mut failed = compiled.push_matcher();
+        assert_eq!(failed.feed("x"), Status::Failed);
+        assert_eq!(failed.rest(), "x");
+    }
+
+    #[test]
+    fn anchors_wait_for_next_char() {
+        let word = (Pattern(char::is_alphabetic) * (1..)).lazy() & word_boundary();
+        let compiled = word.compile().unwrap();
+        let mut matcher = compiled.push_matcher();
+        assert_eq!(matcher.feed("ab"), Status::Incomplete);
+        assert_eq!(matcher.feed("c d"), Status::Matched);
+        assert_eq!(matcher.matched(), Some("abc"));
+
+        let whole = Pattern("ab") & end();
+        let compiled = whole.compile().unwrap();
+        let mut matcher = compiled.push_matcher();
+        assert_eq!(matcher.feed("ab"), Status::Incomplete);
+        assert_eq!(matcher.finish(), Status::Matched);
+
+        let mut longer = compiled.push_matcher();
+        assert_eq!(longer.feed("ab"), Status::Incomplete);
+        assert_eq!(longer.feed("c"), Status::Failed);
+    }
+
+    #[test]
+    fn pipelined_messages() {
+        let length = cap_named("len", Pattern(|c: char| c.is_ascii_digit()) * (1..));
+        let body = cap_named("body", Pattern(|c: char| c != '\n') * ..);
+        let message = length & ':' & body & '\n';
+        let compiled = message.compile().unwrap();
+
+        let mut matcher = compiled.push_matcher();
+        assert_eq!(matcher.feed("3:a"), Status::Incomplete);
+        assert_eq!(matcher.feed("bc\n5:"), Status::Matched);
+        assert_eq!(matcher.captures().unwrap().name("body"), Some("abc"));
+
+        assert_eq!(matcher.consume(), Status::Incomplete);
+        assert_eq!(matcher.feed("hello\n"), Status::Matched);
+        assert_eq!(matcher.captures().unwrap().name("len"), Some("5"));
+
+        assert_eq!(matcher.consume(), Status::Incomplete);
+        assert_eq!(matcher.finish(), Status::Failed);
+    }
+
+    #[test]
+    fn long_input_one_byte_at_a_time() {
+        let body = Pattern('{') & (Pattern(|c: char| c != '}') * ..) & '}';
+        let compiled = body.compile().unwrap();
+        let mut matcher = compiled.push_matcher();
+
+        assert_eq!(matcher.feed("{"), Status::Incomplete);
+        for _ in 0..200_000 {
+            assert_eq!(matcher.feed("x"), Status::Incomplete);
+        }
+        assert_eq!(matcher.feed("}"), Status::Matched);
+        assert_eq!(matcher.matched().unwrap().len(), 200_002);
+    }
+}
@@ -0,0 +1,155 @@
+
+/// Input text which patterns are matched against
+///
+/// Implemented by `str` and `[u8]`.
+/// Positions are offsets in bytes, a `str` is split only at char boundaries.
+/// Bytes are seen by anchors as ASCII chars,
+/// any non-ASCII byte is seen as `U+FFFD` which is not a word char.
+///
+pub trait Haystack: 'static + ToOwned {
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn split_at(&self, mid: usize) -> (&Self, &Self);
+
+    fn as_bytes(&self) -> &[u8];
+
+    /// Returns the length of the beginning of the text which is equal to the prefix
+    ///
+    /// `nocase` compares text ignoring case.
+    ///
+    fn scan_prefix(&self, prefix: &Self, nocase: bool) -> Option<usize>;
+
+    /// Position of the unit (char or byte) which ends at the position
+    fn prev_pos(&self, pos: usize) -> Option<usize>;
+
+    /// Position right after the unit (char or byte) which starts at the position
+    fn next_pos(&self, pos: usize) -> Option<usize>;
+
+    fn first_char(&self) -> Option<char>;
+
+    fn last_char(&self) -> Option<char>;
+
+    /// Longest beginning of the bytes which is a valid text
+    ///
+    /// It's `Ok` if the bytes after it can be completed by the following input,
+    /// e.g. a char split between two chunks of a stream,
+    /// and `Err` if they are invalid whatever follows them.
+    ///
+    fn valid_prefix(bytes: &[u8]) -> Result<&Self, &Self>;
+}
+
+impl Haystack for str {
+    fn len(&self) -> usize {
+        str::len(self)
+    }
+
+    fn split_at(&self, mid: usize) -> (&Self, &Self) {
+        str::split_at(self, mid)
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        str::as_bytes(self)
+    }
+
+    fn scan_prefix(&self, prefix: &Self, nocase: bool) -> Option<usize> {
+        use crate::scan_term::ScanTerm;
+
+        if nocase {
+            prefix.scan_term_nocase(self)
+        } else {
+            prefix.scan_term(self)
+        }
+    }
+
+    fn prev_pos(&self, pos: usize) -> Option<usize> {
+        self[..pos].char_indices().next_back().map(|(prev, _)| prev)
+    }
+
+    fn next_pos(&self, pos: usize) -> Option<usize> {
+        self[pos..].chars().next().map(|ch| pos + ch.len_utf8())
+    }
+
+    fn first_char(&self) -> Option<char> {
+        self.chars().next()
+    }
+
+    fn last_char(&self) -> Option<char> {
+        self.chars().next_back()
+    }
+
+    fn valid_prefix(bytes: &[u8]) -> Result<&Self, &Self> {
+        str::from_utf8(bytes).or_else(|err| {
+            let valid = str::from_utf8(&bytes[..err.valid_up_to()]).unwrap_or_default();
+            match err.error_len() {
+                None => Ok(valid),
+                Some(_) => Err(valid),
+            }
+        })
+    }
+}
+
+fn byte_char(byte: u8) -> char {
+    if byte.is_ascii() {
+        byte as char
+    } else {
+        char::REPLACEMENT_CHARACTER
+    }
+}
+
+impl Haystack for [u8] {
+    fn len(&self) -> usize {
+        <[u8]>::len(self)
+    }
+
+    fn split_at(&self, mid: usize) -> (&Self, &Self) {
+        <[u8]>::split_at(self, mid)
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        self
+    }
+
+    fn scan_prefix(&self, prefix: &Self, nocase: bool) -> Option<usize> {
+        let head = self.get(..prefix.len())?;
+
+        let equal = if nocase {
+            head.eq_ignore_ascii_case(prefix)
+        } else {
+            head == prefix
+        };
+
+        if equal {
+            Some(prefix.len())
+        } else {
+            None
+        }
+    }
+
+    fn prev_pos(&self, pos: usize) -> Option<usize> {
+        pos.checked_sub(1)
+    }
+
+    fn next_pos(&self, pos: usize) -> Option<usize> {
+        if pos < self.len() {
+            Some(pos + 1)
+        } else {
+            None
+        }
+    }
+
+    fn first_char(&self) -> Option<char> {
+        self.first().copied().map(byte_char)
+    }
+
+    fn last_char(&self) -> Option<char> {
+        self.last().copied().map(byte_char)
+    }
+
+    fn valid_prefix(bytes: &[u8]) -> Result<&Self, &Self> {
+        Ok(bytes)
+    }
+}
@@ -0,0 +1,504 @@
+
+use std::cmp::Reverse;
+use std::str;
+use crate::haystack::Haystack;
+use crate::scan_term::ScanTerm;
+use crate::capture::{Capture, CaptureResult, CaptureFn, Context};
+use crate::captures::{Captures, KeywordEntry};
+use crate::patterns::Pattern;
+
+/// Which entry wins if several entries match the text
+///
+/// `KeywordEntries` try the other entries in the same order if the rest of expression fails.
+///
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum KeywordMode {
+    /// The entry listed first, like an alternation of the entries
+    First,
+
+    /// The longest entry
+    Longest,
+}
+
+#[derive(Clone, Debug, Default)]
+struct Node {
+    children: Vec<(char, usize)>,
+    entry: Option<usize>,
+}
+
+/// Set of literal strings which matches any of them
+///
+/// The strings are compiled into a trie,
+/// so the matching time doesn't depend on the number of entries.
+/// Entries are indexed in order of listing.
+///
+/// Keywords are a term which matches the preferred entry of the mode, in text and in bytes.
+/// Like other terms they match once, see `capture_entries` for a pattern
+/// which tries the other entries too and captures the index of the matched entry.
+///
+/// # Examples
+/// ```
+/// # use rep::{Pattern, ScanTerm, Keywords};
+/// let codes = Keywords::new(&["US", "UA", "GB"]);
+/// let matches: Vec<&str> = Pattern(codes.clone()).matched_strs("UA, GB, DE").collect();
+/// assert_eq!(matches, ["UA", "GB"]);
+/// assert_eq!(codes.entry("GB"), Some(2));
+/// assert_eq!(codes.scan_term(&b"GB"[..]), Some(2));
+/// ```
+///
+#[derive(Clone, Debug)]
+pub struct Keywords {
+    nodes: Vec<Node>,
+    mode: KeywordMode,
+}
+
+/// The first char of the bytes if they begin with a valid UTF-8 char
+fn first_char(bytes: &[u8]) -> Option<char> {
+    let head = &bytes[..bytes.len().min(4)];
+    let valid = match str::from_utf8(head) {
+        Ok(valid) => valid,
+        Err(err) => str::from_utf8(&head[..err.valid_up_to()]).ok()?,
+    };
+
+    valid.chars().next()
+}
+
+/// The char as a haystack
+fn char_text<H: ?Sized + Haystack>(ch: char, buffer: &mut [u8; 4]) -> &H {
+    H::valid_prefix(ch.encode_utf8(buffer).as_bytes()).unwrap_or_else(|valid| valid)
+}
+
+impl Keywords {
+
+    /// Keywords which prefer the entry listed first
+    pub fn new<I, S>(entries: I) -> Keywords where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut keywords = Keywords {
+            nodes: vec![Node::default()],
+            mode: KeywordMode::First,
+        };
+
+        for (index, entry) in entries.into_iter().enumerate() {
+            keywords.insert(entry.as_ref(), index);
+        }
+
+        keywords
+    }
+
+    /// Keywords which prefer the longest entry
+    ///
+    /// # Examples
+    /// ```
+    /// # use rep::{ScanTerm, Keywords};
+    /// let first = Keywords::new(&["in", "int"]);
+    /// assert_eq!(first.scan_term("int x"), Some(2));
+    ///
+    /// let longest = Keywords::longest(&["in", "int"]);
+    /// assert_eq!(longest.scan_term("int x"), Some(3));
+    /// ```
+    ///
+    pub fn longest<I, S>(entries: I) -> Keywords where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        Keywords {
+            mode: KeywordMode::Longest,
+            ..Keywords::new(entries)
+        }
+    }
+
+    pub fn mode(&self) -> KeywordMode {
+        self.mode
+    }
+
+    /// Pattern which tries every matched entry in order of the mode and captures its index
+    ///
+    /// The other entries are tried if the rest of expression fails,
+    /// like branches of an alternation.
+    ///
+    /// # Examples
+    /// ```
+    /// # use rep::{Pattern, Keywords};
+    /// let prefix = Keywords::new(&["a", "ab"]).capture_entries() & 'c';
+    /// assert!(prefix.test("abc"));
+    /// assert!(!(Pattern(Keywords::new(&["a", "ab"])) & 'c').test("abc"));
+    ///
+    /// let caps = Keywords::new(&["US", "UA", "GB"]).capture_entries().matches("GB").next().unwrap();
+    /// assert_eq!(caps.entry(), Some(2));
+    /// ```
+    ///
+    pub fn capture_entries(self) -> Pattern<KeywordEntries> {
+        Pattern(KeywordEntries(self))
+    }
+
+    fn insert(&mut self, entry: &str, index: usize) {
+        let mut node = 0;
+
+        for ch in entry.chars() {
+            node = match self.find_child(node, ch) {
+                Ok(pos) => self.nodes[node].children[pos].1,
+                Err(pos) => {
+                    let child = self.nodes.len();
+                    self.nodes.push(Node::default());
+                    self.nodes[node].children.insert(pos, (ch, child));
+                    child
+                },
+            };
+        }
+
+        // A duplicate keeps the index of the first listing
+        self.nodes[node].entry.get_or_insert(index);
+    }
+
+    fn find_child(&self, node: usize, ch: char) -> Result<usize, usize> {
+        self.nodes[node].children.binary_search_by_key(&ch, |&(c, _)| c)
+    }
+
+    /// Index of the entry equal to the text
+    pub fn entry(&self, text: &str) -> Option<usize> {
+        let mut node = 0;
+
+        for ch in text.chars() {
+            let pos = self.find_child(node, ch).ok()?;
+            node = self.nodes[node].children[pos].1;
+        }
+
+        self.nodes[node].entry
+    }
+
+    /// Finds the preferred entry at the beginning of the text
+    ///
+    /// Returns the index of the entry and the length of the matched text (in bytes).
+    ///
+    /// # Examples
+    /// ```
+    /// # use rep::Keywords;
+    /// let keywords = Keywords::new(&["fn", "for", "if"]);
+    /// assert_eq!(keywords.scan_entry("for x in"), Some((1, 3)));
+    /// assert_eq!(keywords.scan_entry("while"), None);
+    /// assert_eq!(keywords.scan_entry(&b"if x"[..]), Some((2, 2)));
+    /// ```
+    ///
+    pub fn scan_entry<H: ?Sized + Haystack>(&self, text: &H) -> Option<(usize, usize)> {
+        self.preferred(text, false)
+    }
+
+    /// Finds the preferred entry at the beginning of the text ignoring case
+    ///
+    /// Bytes ignore case of ASCII letters only, like other byte terms.
+    ///
+    pub fn scan_entry_nocase<H: ?Sized + Haystack>(&self, text: &H) -> Option<(usize, usize)> {
+        self.preferred(text, true)
+    }
+
+    fn preferred<H: ?Sized + Haystack>(&self, text: &H, nocase: bool) -> Option<(usize, usize)> {
+        let mut best: Option<(usize, usize)> = None;
+
+        self.walk(0, text, 0, nocase, &mut |entry, len| {
+            let better = match (best, self.mode) {
+                (None, _) => true,
+                (Some((best_entry, _)), KeywordMode::First) => entry < best_entry,
+                (Some((best_entry, best_len)), KeywordMode::Longest) => (Reverse(len), entry) < (Reverse(best_len), best_entry),
+            };
+
+            if better {
+                best = Some((entry, len));
+            }
+        });
+
+        best
+    }
+
+    /// Every entry at the beginning of the text in order of preference
+    fn candidates<H: ?Sized + Haystack>(&self, text: &H, nocase: bool) -> Vec<(usize, usize)> {
+        let mut found = Vec::new();
+        self.walk(0, text, 0, nocase, &mut |entry, len| found.push((entry, len)));
+
+        match self.mode {
+            KeywordMode::First => found.sort_unstable(),
+            KeywordMode::Longest => found.sort_unstable_by_key(|&(entry, len)| (Reverse(len), entry)),
+        }
+
+        found
+    }
+
+    /// Passes every entry found at `pos` of the text to `f` along with the end of it
+    fn walk<H: ?Sized + Haystack>(
+        &self,
+        node: usize,
+        text: &H,
+        pos: usize,
+        nocase: bool,
+        f: &mut dyn FnMut(usize, usize),
+    ) {
+        if let Some(entry) = self.nodes[node].entry {
+            f(entry, pos);
+        }
+
+        let rest = text.split_at(pos).1;
+
+        if !nocase {
+            if let Some(ch) = first_char(rest.as_bytes()) {
+                if let Ok(found) = self.find_child(node, ch) {
+                    self.walk(self.nodes[node].children[found].1, text, pos + ch.len_utf8(), nocase, f);
+                }
+            }
+
+            return;
+        }
+
+        // Several children can be equal to the char ignoring case,
+        // the haystack decides how the case is ignored
+        for &(child_ch, child) in &self.nodes[node].children {
+            let mut buffer = [0; 4];
+            if let Some(len) = rest.scan_prefix(char_text(child_ch, &mut buffer), true) {
+                self.walk(child, text, pos + len, nocase, f);
+            }
+        }
+    }
+
+    /// Checks the text ends inside of the trie, so more text could match a longer entry
+    fn reaches_end<H: ?Sized + Haystack>(&self, node: usize, text: &H, nocase: bool) -> bool {
+        if text.is_empty() {
+            return !self.nodes[node].children.is_empty();
+        }
+
+        self.nodes[node].children.iter().any(|&(child_ch, child)| {
+            let mut buffer = [0; 4];
+            let literal = char_text::<H>(child_ch, &mut buffer);
+
+            match text.scan_prefix(literal, nocase) {
+                Some(len) => self.reaches_end(child, text.split_at(len).1, nocase),
+
+                // The text ends in the middle of the char
+                None => literal.scan_prefix(text, nocase).is_some_and(|len| len < literal.len()),
+            }
+        })
+    }
+
+    /// The length of the longest entry, every char is counted with `width`
+    fn width(&self, width: impl Fn(char) -> usize) -> usize {
+        // Children are always added after their parents
+        let mut widths = vec![0; self.nodes.len()];
+        for (node, Node { children, .. }) in self.nodes.iter().enumerate() {
+            for &(ch, child) in children {
+                widths[child] = widths[node] + width(ch);
+            }
+        }
+
+        widths.into_iter().max().unwrap_or(0)
+    }
+}
+
+impl ScanTerm for Keywords {
+    fn scan_term(&self, text: &str) -> Option<usize> {
+        self.scan_entry(text).map(|(_, len)| len)
+    }
+
+    fn scan_term_nocase(&self, text: &str) -> Option<usize> {
+        self.scan_entry_nocase(text).map(|(_, len)| len)
+    }
+
+    fn is_incomplete(&self, text: &str, nocase: bool) -> bool {
+        self.reaches_end(0, text, nocase)
+    }
+
+    /// The number of chars of the longest entry
+    fn term_width(&self) -> Option<usize> {
+        Some(self.width(|_| 1))
+    }
+}
+
+/// Entries are matched with their UTF-8 encoding
+impl ScanTerm<[u8]> for Keywords {
+    fn scan_term(&self, text: &[u8]) -> Option<usize> {
+        self.scan_entry(text).map(|(_, len)| len)
+    }
+
+    fn scan_term_nocase(&self, text: &[u8]) -> Option<usize> {
+        self.scan_entry_nocase(text).map(|(_, len)| len)
+    }
+
+    fn is_incomplete(&self, text: &[u8], nocase: bool) -> bool {
+        self.reaches_end(0, text, nocase)
+    }
+
+    /// The number of bytes of the longest entry
+    fn term_width(&self) -> Option<usize> {
+        Some(self.width(char::len_utf8))
+    }
+}
+
+/// Keywords which match every entry found at the beginning of the text
+///
+/// Entries are tried in order of the mode, like branches of an alternation,
+/// and captures hold the index of the matched entry. See `Keywords::capture_entries`.
+///
+#[derive(Clone, Debug)]
+pub struct KeywordEntries(pub Keywords);
+
+impl<'a, H: ?Sized + Haystack> Capture<'a, H> for KeywordEntries where
+    Keywords: ScanTerm<H>,
+{
+    type Inner = KeywordEntry;
+
+    fn capture_each(
+        &self,
+        ctx: &mut Context<'a, H>,
+        text: &'a H,
+        f: &mut CaptureFn<'_, 'a, Self::Inner, H>,
+    ) -> bool {
+        if ctx.is_partial() && self.0.is_incomplete(text, ctx.nocase()) {
+            ctx.mark_end();
+        }
+
+        for (entry, len) in self.0.candidates(text, ctx.nocase()) {
+            let (left, rest) = text.split_at(len);
+            let caps = Captures::new(ctx.position(text), left, KeywordEntry::new(entry));
+            if f(ctx, CaptureResult::new(caps, rest)) {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    fn capture_empty(&self, ctx: &Context<'a, H>, text: &'a H) -> CaptureResult<'a, Self::Inner, H> {
+        let caps = Captures::new(ctx.position(text), text.split_at(0).0, KeywordEntry::empty());
+        CaptureResult::new(caps, text)
+    }
+
+    fn max_width(&self) -> Option<usize> {
+        self.0.term_width()
+    }
+
+    fn max_behind(&self) -> Option<usize> {
+        Some(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::patterns::{nocase, end};
+
+    #[test]
+    fn keywords_first() {
+        let keywords = Keywords::new(vec!["a".to_string(), "ab".to_string(), "abc".to_string(), "b".to_string()]);
+        assert_eq!(keywords.scan_entry("abc"), Some((0, 1)));
+        assert_eq!(keywords.scan_entry("b"), Some((3, 1)));
+        assert_eq!(keywords.scan_entry("c"), None);
+        assert_eq!(keywords.scan_entry(""), None);
+
+        let reversed = Keywords::new(["abc", "ab", "a"]);
+        assert_eq!(reversed.scan_entry("abd"), Some((1, 2)));
+        assert_eq!(reversed.scan_entry("abc"), Some((0, 3)));
+        assert_eq!(reversed.scan_term("abc"), Some(3));
+    }
+
+    #[test]
+    fn keywords_longest() {
+        let keywords = Keywords::longest(["a", "abc", "ab"]);
+        assert_eq!(keywords.scan_entry("abcd"), Some((1, 3)));
+        assert_eq!(keywords.scan_entry("abd"), Some((2, 2)));
+        assert_eq!(keywords.scan_entry("ax"), Some((0, 1)));
+        assert_eq!(keywords.scan_entry("x"), None);
+
+        assert!(keywords.is_incomplete("ab", false));
+        assert!(keywords.is_incomplete("AB", true));
+        assert!(!keywords.is_incomplete("abc", false));
+        assert!(!keywords.is_incomplete("ax", false));
+        assert_eq!(ScanTerm::<str>::term_width(&keywords), Some(3));
+    }
+
+    #[test]
+    fn keywords_entries() {
+        let entries: &[&str] = &["ж", "жук", "ж", ""];
+        let keywords = Keywords::new(entries);
+        assert_eq!(keywords.entry("ж"), Some(0));
+        assert_eq!(keywords.entry("жук"), Some(1));
+        assert_eq!(keywords.entry("жу"), None);
+        assert_eq!(keywords.entry(""), Some(3));
+        assert_eq!(keywords.scan_entry("жуки"), Some((0, 2)));
+        assert_eq!(keywords.scan_entry("x"), Some((3, 0)));
+    }
+
+    #[test]
+    fn keywords_nocase() {
+        let keywords = Keywords::longest(["select", "SEL", "Kelvin"]);
+        assert_eq!(keywords.scan_entry_nocase("SELECT"), Some((0, 6)));
+        assert_eq!(keywords.scan_entry_nocase("sElEcX"), Some((1, 3)));
+        assert_eq!(keywords.scan_entry_nocase("\u{212A}ELVIN"), Some((2, 8)));
+
+        let pattern = nocase(Keywords::new(["from", "where"]));
+        let matches: Vec<&str> = pattern.clone().matched_strs("FROM t WHERE").collect();
+        assert_eq!(matches, ["FROM", "WHERE"]);
+
+        let entries = nocase(Keywords::new(["from", "where"]).capture_entries());
+        let found: Vec<Option<usize>> = entries.matches("WHERE From").map(|caps| caps.entry()).collect();
+        assert_eq!(found, [Some(1), Some(0)]);
+    }
+
+    #[test]
+    fn keywords_backtracking() {
+        let first = Keywords::new(["a", "ab"]).capture_entries() & 'c';
+        assert!(first.test("abc"));
+        assert!(first.test("ac"));
+        assert!(!first.test("abd"));
+
+        let longest = Keywords::longest(["in", "int", "i"]).capture_entries() & 't' & end();
+        assert!(longest.test("int"));
+        assert!(longest.test("it"));
+
+        let both = nocase(Keywords::new(["SEL", "select"]).capture_entries()) & "ion";
+        assert!(both.test("SELECTion"));
+        assert!(!both.test("SELECTor"));
+
+        // The term matches the preferred entry only
+        let term = Pattern(Keywords::new(["a", "ab"])) & 'c';
+        assert!(!term.test("abc"));
+        assert!(term.test("ac"));
+    }
+
+    #[test]
+    fn keywords_bytes() {
+        let keywords = Keywords::longest(["GET", "жук", "ж"]);
+        assert_eq!(keywords.scan_entry(&b"GET /"[..]), Some((0, 3)));
+        assert_eq!(keywords.scan_entry("жуки".as_bytes()), Some((1, 6)));
+        assert_eq!(keywords.scan_entry(&[0xD0, 0xB6, 0xFF][..]), Some((2, 2)));
+        assert_eq!(keywords.scan_entry(&[0xFF][..]), None);
+        assert_eq!(ScanTerm::<[u8]>::term_width(&keywords), Some(6));
+
+        // Case of ASCII letters only is ignored in bytes
+        assert_eq!(keywords.scan_entry_nocase(&b"get"[..]), Some((0, 3)));
+        assert_eq!(keywords.scan_entry_nocase("ЖУК".as_bytes()), None);
+
+        // The input may end in the middle of a char
+        assert!(keywords.is_incomplete(&[0xD0][..], false));
+        assert!(keywords.is_incomplete(&b"GE"[..], false));
+        assert!(!keywords.is_incomplete(&b"GX"[..], false));
+
+        let method = Pattern(Keywords::new(["GET", "POST"])) & b' ';
+        assert!(method.test(&b"POST "[..]));
+        assert!(!method.test(&b"PUT "[..]));
+
+        let entries = Keywords::new(["a", "ab"]).capture_entries() & b'c';
+        let caps = entries.matches(&b"xabc"[..]).next().unwrap();
+        assert_eq!(caps.captured_str, &b"abc"[..]);
+    }
+
+    #[test]
+    fn keywords_pattern() {
+        let countries: Vec<String> = (b'A'..=b'Z')
+            .flat_map(|a| (b'A'..=b'Z').map(move |b| format!("{}{}", a as char, b as char)))
+            .collect();
+
+        let code = Pattern(Keywords::new(&countries)) & ':';
+        assert!(code.test("UA:"));
+        assert!(code.test("ZZ:"));
+        assert!(!code.test("U1:"));
+        assert!(!code.test("ua:"));
+    }
+}
@@ -2,11 +2,52 @@
 mod captures;
 mod patterns;
 mod scan_term;
+mod haystack;
 mod capture;
+mod case_fold;
+mod char_set;
+mod keywords;
+mod unicode;
 mod match_iterator;
+mod read_matches;
+mod push_matcher;
+mod replace;
+mod split;
+mod compile;
 
 pub use scan_term::ScanTerm;
-pub use patterns::{Pattern, cap};
+pub use haystack::Haystack;
+pub use read_matches::{ReadMatches, StreamMatch};
+pub use push_matcher::{PushMatcher, Status};
+pub use captures::{Captures, Span};
+pub use replace::{Replacer, NoExpand};
+pub use split::Split;
+pub use compile::{Compile, CompileError, Compiler, Compiled, CompiledMatch, CompiledMatches, CompiledPushMatcher, LazyDfa};
+pub use char_set::CharSet;
+pub use keywords::{Keywords, KeywordEntries, KeywordMode};
+pub use unicode::{GeneralCategory, Script, Property, UNICODE_VERSION};
+pub use patterns::{
+    Pattern,
+    Anchor,
+    cap,
+    cap_named,
+    backref,
+    atomic,
+    longest,
+    ahead,
+    not_ahead,
+    behind,
+    not_behind,
+    start,
+    end,
+    line_start,
+    line_end,
+    word_boundary,
+    not_word_boundary,
+    nocase,
+    one_of,
+    none_of,
+};
 
 #[cfg(test)]
 mod tests {
@@ -15,7 +56,7 @@ mod tests {
     #[test]
     fn parse_hex() {
         let number = Pattern('A'..='F') | ('0'..='9');
-        let hex = Pattern('0') & 'x' & number * (1..);
+        let hex = Pattern('0') & 'x' & (number * (1..));
 
         assert!(hex.test("0xFF94"));
         assert!(hex.test("0x12AB"));
@@ -31,7 +72,7 @@ mod tests {
     #[test]
     fn parse() {
         let space = Pattern(' ') * ..;
-        let name = Pattern(char::is_alphabetic) & Pattern(char::is_alphabetic) * ..;
+        let name = Pattern(char::is_alphabetic) & (Pattern(char::is_alphabetic) * ..);
         let arg = name & ',' & space;
         let args = (arg * ..) & name & space | space;
         let func = name & space & '(' & space & args & ')' & space & ';';
@@ -45,4 +86,42 @@ mod tests {
         assert!(!func.test("func(bar, num, str)"));
         assert!(!func.test("func(bar str);"));
     }
+
+    #[test]
+    fn parse_bytes() {
+        let method = Pattern(b"GET") | b"POST";
+        let path = Pattern(|b: u8| b != b' ' && b != b'\r') * (1..);
+        let request = cap_named("method", method) & b' ' & cap_named("path", path) & b"\r\n";
+
+        let line: &[u8] = b"GET /index.html\r\n";
+        assert!(request.test(line));
+        assert!(!request.test(&b"PUT /\r\n"[..]));
+
+        let caps = request.matches(line).next().unwrap();
+        assert_eq!(caps.name("method"), Some(&b"GET"[..]));
+        assert_eq!(caps.name("path"), Some(&b"/index.html"[..]));
+        assert_eq!(caps.span().range(), 0..line.len());
+
+        // Bytes which are not valid UTF-8 are matched like any other bytes
+        let invalid: &[u8] = &[0xFF, b'a', 0xC3, b'b', b'c', 0xFE];
+        let letters = Pattern(b'a'..=b'z') * (1..);
+        let words: Vec<&[u8]> = letters.clone().matched_strs(invalid).collect();
+        assert_eq!(words, [&b"a"[..], &b"bc"[..]]);
+
+        let high = Pattern(|b: u8| b >= 0x80);
+        let indices: Vec<(usize, &[u8])> = high.match_indices(invalid).collect();
+        assert_eq!(indices, [(0, &[0xFF][..]), (2, &[0xC3][..]), (5, &[0xFE][..])]);
+
+        let word = word_boundary() & letters & word_boundary();
+        let words: Vec<&[u8]> = word.matched_strs(invalid).collect();
+        assert_eq!(words, [&b"a"[..], &b"bc"[..]]);
+
+        let quote = cap(Pattern(b'\'') | b'"');
+        let string = quote & (Pattern(|b: u8| b != b'\'' && b != b'"') * ..) & backref(&quote);
+        assert!(string.test(&b"'\xFF\x00'"[..]));
+        assert!(!string.test(&b"'\xFF\x00\""[..]));
+
+        let header = nocase(Pattern(b"content-length:"));
+        assert!(header.test(&b"Content-Length:"[..]));
+    }
 }
@@ -1,36 +1,94 @@
 
-use crate::capture::Capture;
+use crate::haystack::Haystack;
+use crate::capture::{Capture, CaptureResult, Context};
 use crate::captures::Captures;
 
-pub struct MatchIterator<'a, P> {
+pub struct MatchIterator<'a, P, H: ?Sized = str> {
     pattern: P,
-    rest: &'a str,
+    ctx: Context<'a, H>,
+    rest: &'a H,
+
+    /// The last match is empty and ends at the rest (or starts at it if matches overlap),
+    /// so the next search starts from the next char
+    skip: bool,
+    done: bool,
+
+    /// Every position is tried, even inside of the previous match
+    overlapping: bool,
 }
 
-impl<'a, P: Capture<'a>> MatchIterator<'a, P> {
-    pub fn new(pattern: P, text: &'a str) -> MatchIterator<'a, P> {
+impl<'a, P: Capture<'a, H>, H: ?Sized + Haystack> MatchIterator<'a, P, H> {
+    pub fn new(pattern: P, text: &'a H) -> MatchIterator<'a, P, H> {
         MatchIterator {
             pattern,
+            ctx: Context::new(text),
             rest: text,
+            skip: false,
+            done: false,
+            overlapping: false,
+        }
+    }
+
+    pub fn overlapping(pattern: P, text: &'a H) -> MatchIterator<'a, P, H> {
+        MatchIterator {
+            overlapping: true,
+            ..MatchIterator::new(pattern, text)
+        }
+    }
+
+    /// Steps the rest by one char (or byte)
+    fn step(&mut self) -> bool {
+        match self.rest.next_pos(0) {
+            Some(next) => {
+                self.rest = self.rest.split_at(next).1;
+                true
+            },
+            None => false,
         }
     }
 }
 
-impl<'a, P: Capture<'a>> Iterator for MatchIterator<'a, P> {
-    type Item = Captures<'a, P::Inner>;
+impl<'a, P: Capture<'a, H>, H: ?Sized + Haystack> Iterator for MatchIterator<'a, P, H> {
+    type Item = Captures<'a, P::Inner, H>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        loop {
-            match self.pattern.capture(self.rest) {
-                Some(cap) => {
-                    self.rest = cap.rest;
-                    break Some(cap.captures)
-                },
-                _ if self.rest.len() != 0 => {
-                    self.rest = &self.rest[1..];
-                },
-                _ => break None,
-            }
+        if self.done || self.skip && !self.step() {
+            self.done = true;
+            return None;
+        }
+
+        match find(&self.pattern, &mut self.ctx, self.rest) {
+            Some(cap) if self.overlapping => {
+                self.rest = self.ctx.rest_at(cap.captures.start);
+                self.skip = true;
+                Some(cap.captures)
+            },
+            Some(cap) => {
+                self.skip = cap.captures.captured_len() == 0;
+                self.rest = cap.rest;
+                Some(cap.captures)
+            },
+            None => {
+                self.done = true;
+                None
+            },
+        }
+    }
+}
+
+/// Finds the first match of the pattern in the rest of text
+///
+/// Positions are tried from left to right, stepping by a char (or a byte).
+///
+pub fn find<'a, P, H>(pattern: &P, ctx: &mut Context<'a, H>, mut rest: &'a H) -> Option<CaptureResult<'a, P::Inner, H>> where
+    P: Capture<'a, H>,
+    H: ?Sized + Haystack,
+{
+    loop {
+        if let Some(cap) = pattern.capture(ctx, rest) {
+            return Some(cap);
         }
+
+        rest = rest.split_at(rest.next_pos(0)?).1;
     }
 }
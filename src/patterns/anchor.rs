@@ -0,0 +1,171 @@
+
+use crate::haystack::Haystack;
+use crate::capture::{Capture, CaptureResult, CaptureFn, Context};
+use crate::captures::{Captures, NoneCapture};
+
+/// Zero-width anchor
+///
+/// Anchors match an empty string at specific positions of the input.
+/// Lines are terminated with `\n`, `\r\n` or `\r`,
+/// and an anchor never matches between `\r` and `\n`.
+///
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Anchor {
+    /// Beginning of the input
+    Start,
+
+    /// End of the input
+    End,
+
+    /// Beginning of the input or of a line
+    LineStart,
+
+    /// End of the input or of a line
+    LineEnd,
+
+    /// Position between a word char and a non-word char (or the input edge)
+    WordBoundary,
+
+    /// Position which is not a word boundary
+    NotWordBoundary,
+}
+
+fn is_word(ch: char) -> bool {
+    ch.is_alphanumeric() || ch == '_'
+}
+
+impl Anchor {
+
+    /// Checks the anchor matches between `before` and `after` texts
+    pub fn is_at<H: ?Sized + Haystack>(self, before: &H, after: &H) -> bool {
+        let prev = before.last_char();
+        let next = after.first_char();
+        let prev_word = prev.is_some_and(is_word);
+        let next_word = next.is_some_and(is_word);
+
+        match self {
+            Anchor::Start => prev.is_none(),
+            Anchor::End => next.is_none(),
+            Anchor::LineStart => match prev {
+                None | Some('\n') => true,
+                Some('\r') => next != Some('\n'),
+                _ => false,
+            },
+            Anchor::LineEnd => match next {
+                None | Some('\r') => true,
+                Some('\n') => prev != Some('\r'),
+                _ => false,
+            },
+            Anchor::WordBoundary => prev_word != next_word,
+            Anchor::NotWordBoundary => prev_word == next_word,
+        }
+    }
+}
+
+impl<'a, H: ?Sized + Haystack> Capture<'a, H> for Anchor {
+    type Inner = NoneCapture;
+
+    fn capture_each(
+        &self,
+        ctx: &mut Context<'a, H>,
+        text: &'a H,
+        f: &mut CaptureFn<'_, 'a, Self::Inner, H>,
+    ) -> bool {
+        // Every anchor but the start of input looks at the next char
+        if text.is_empty() && *self != Anchor::Start {
+            ctx.mark_end();
+        }
+
+        if !self.is_at(ctx.before(text), text) {
+            return false;
+        }
+
+        let res = self.capture_empty(ctx, text);
+        f(ctx, res)
+    }
+
+    fn capture_empty(&self, ctx: &Context<'a, H>, text: &'a H) -> CaptureResult<'a, Self::Inner, H> {
+        CaptureResult::new(Captures::without_capture(ctx.position(text), text.split_at(0).0), text)
+    }
+
+    fn max_width(&self) -> Option<usize> {
+        Some(0)
+    }
+
+    /// Anchors look at the char before the position
+    fn max_behind(&self) -> Option<usize> {
+        Some(1)
+    }
+    fn is_simple(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{
+        Pattern,
+        start,
+        end,
+        line_start,
+        line_end,
+        word_boundary,
+        not_word_boundary,
+    };
+
+    #[test]
+    fn input_anchors() {
+        let first = start() & "xy";
+        let first_matches: Vec<&str> = first.matched_strs("xy xy").collect();
+        assert_eq!(first_matches, ["xy"]);
+
+        let last = Pattern("xy") & end();
+        let last_matches: Vec<&str> = last.matched_strs("xy xy").collect();
+        assert_eq!(last_matches, ["xy"]);
+
+        let whole = start() & "abc" & end();
+        assert!(whole.test("abc"));
+        assert!(!whole.test("abcd"));
+
+        let empty = start() & end();
+        assert!(empty.test(""));
+        assert!(!empty.test("a"));
+    }
+
+    #[test]
+    fn line_anchors() {
+        let word = Pattern(char::is_alphabetic) * (1..);
+
+        let first = line_start() & word;
+        let first_words: Vec<&str> = first.matched_strs("ab cd\nef gh\r\nij\rkl").collect();
+        assert_eq!(first_words, ["ab", "ef", "ij", "kl"]);
+
+        let last = (Pattern(char::is_alphabetic) * (1..)) & line_end();
+        let last_words: Vec<&str> = last.matched_strs("ab cd\nef gh\r\nij\rkl").collect();
+        assert_eq!(last_words, ["cd", "gh", "ij", "kl"]);
+
+        let crlf = Pattern('\r') & line_end() & '\n';
+        assert!(!crlf.test("\r\n"));
+
+        let lf = Pattern('\r') & line_start() & '\n';
+        assert!(!lf.test("\r\n"));
+
+        let empty_line = Pattern('\n') & line_start() & line_end() & '\n';
+        assert!(empty_line.test("\n\n"));
+    }
+
+    #[test]
+    fn word_anchors() {
+        let xy = word_boundary() & "xy" & word_boundary();
+        let xy_matches: Vec<&str> = xy.matched_strs("ab cxy yx xy x xxy.").collect();
+        assert_eq!(xy_matches, ["xy"]);
+
+        let inner = not_word_boundary() & 'x' & not_word_boundary();
+        let inner_matches: Vec<&str> = inner.matched_strs("x axb _x_ x").collect();
+        assert_eq!(inner_matches, ["x", "x"]);
+
+        let unicode = word_boundary() & "мир" & word_boundary();
+        assert!(unicode.test("мир"));
+        assert!(!(Pattern('ы') & word_boundary() & "мир").test("ымир"));
+    }
+}
@@ -1,31 +1,49 @@
 
-use crate::capture::{Capture, CaptureResult};
+use crate::haystack::Haystack;
+use crate::capture::{Capture, CaptureResult, CaptureFn, Context};
 use crate::captures::{DoubleCaptures, Captures};
 
 #[derive(Copy, Clone)]
 pub struct AndPattern<A, B>(pub A, pub B);
 
-impl<'a, A: Capture<'a>, B: Capture<'a>> Capture<'a> for AndPattern<A, B> {
+impl<'a, H: ?Sized + Haystack, A: Capture<'a, H>, B: Capture<'a, H>> Capture<'a, H> for AndPattern<A, B> {
     type Inner = DoubleCaptures<A::Inner, B::Inner>;
 
-    fn capture(&self, text: &'a str) -> Option<CaptureResult<'a, Self::Inner>> {
-        let (caps_a, rest) = self.0.capture(text)?.into();
-        let (caps_b, rest) = self.1.capture(rest)?.into();
-
-        let captured_str = &text[..caps_a.captured_len() + caps_b.captured_len()];
-        let inner = DoubleCaptures::new(caps_a.inner, caps_b.inner);
-        let caps = Captures::new(captured_str, inner);
-
-        Some(CaptureResult::new(caps, rest))
+    fn capture_each(
+        &self,
+        ctx: &mut Context<'a, H>,
+        text: &'a H,
+        f: &mut CaptureFn<'_, 'a, Self::Inner, H>,
+    ) -> bool {
+        self.0.capture_each(ctx, text, &mut |ctx, res_a| {
+            self.1.capture_each(ctx, res_a.rest, &mut |ctx, res_b| {
+                let len = res_a.captures.captured_len() + res_b.captures.captured_len();
+                let inner = DoubleCaptures::new(res_a.captures.inner.clone(), res_b.captures.inner);
+                let caps = Captures::new(res_a.captures.start, text.split_at(len).0, inner);
+                f(ctx, CaptureResult::new(caps, res_b.rest))
+            })
+        })
     }
 
-    fn capture_empty(&self, text: &'a str) -> CaptureResult<'a, Self::Inner> {
-        let inner_a = self.0.capture_empty(text).captures.inner;
-        let inner_b = self.1.capture_empty(text).captures.inner;
+    fn capture_empty(&self, ctx: &Context<'a, H>, text: &'a H) -> CaptureResult<'a, Self::Inner, H> {
+        let inner_a = self.0.capture_empty(ctx, text).captures.inner;
+        let inner_b = self.1.capture_empty(ctx, text).captures.inner;
         let inner = DoubleCaptures::new(inner_a, inner_b);
-        let caps = Captures::new("", inner);
+        let caps = Captures::new(ctx.position(text), text.split_at(0).0, inner);
         CaptureResult::new(caps, text)
     }
+
+    fn is_simple(&self) -> bool {
+        self.0.is_simple() && self.1.is_simple()
+    }
+
+    fn max_width(&self) -> Option<usize> {
+        self.0.max_width()?.checked_add(self.1.max_width()?)
+    }
+
+    fn max_behind(&self) -> Option<usize> {
+        Some(self.0.max_behind()?.max(self.1.max_behind()?))
+    }
 }
 
 #[cfg(test)]
@@ -51,4 +69,24 @@ mod tests {
         assert!(!empty_pattern.test("a"));
         assert!(!empty_pattern.test("b"));
     }
+
+    #[test]
+    fn and_pattern_backtracking() {
+        let a_a = (Pattern('a') * ..) & 'a';
+        assert!(a_a.test("a"));
+        assert!(a_a.test("aa"));
+        assert!(a_a.test("aaaa"));
+        assert!(!a_a.test(""));
+        assert!(!a_a.test("ab"));
+
+        let ab_b = (Pattern("a") | "ab") & 'b';
+        assert!(ab_b.test("ab"));
+        assert!(ab_b.test("abb"));
+        assert!(!ab_b.test("a"));
+
+        let digit = Pattern(|c: char| c.is_ascii_digit());
+        let number = (digit * (1..)) & '.' & (digit * (1..)) & (digit * 2);
+        assert!(number.test("12.345"));
+        assert!(!number.test("12.34"));
+    }
 }
@@ -0,0 +1,68 @@
+
+use crate::haystack::Haystack;
+use crate::capture::{Capture, CaptureResult, CaptureFn, Context};
+
+/// Atomic (possessive) group
+///
+/// The group takes the first match of inner pattern
+/// and never gives it back when the rest of expression fails.
+///
+#[derive(Copy, Clone)]
+pub struct Atomic<S>(pub S);
+
+impl<'a, H: ?Sized + Haystack, S: Capture<'a, H>> Capture<'a, H> for Atomic<S> {
+    type Inner = S::Inner;
+
+    fn capture_each(
+        &self,
+        ctx: &mut Context<'a, H>,
+        text: &'a H,
+        f: &mut CaptureFn<'_, 'a, Self::Inner, H>,
+    ) -> bool {
+        let mut first = None;
+        self.0.capture_each(ctx, text, &mut |ctx, res| {
+            first = Some((ctx.saved_groups(), res));
+            true
+        });
+
+        match first {
+            Some((groups, res)) => ctx.with_saved_groups(groups, |ctx| f(ctx, res)),
+            None => false,
+        }
+    }
+
+    fn capture_empty(&self, ctx: &Context<'a, H>, text: &'a H) -> CaptureResult<'a, Self::Inner, H> {
+        self.0.capture_empty(ctx, text)
+    }
+
+    fn max_width(&self) -> Option<usize> {
+        self.0.max_width()
+    }
+
+    fn max_behind(&self) -> Option<usize> {
+        self.0.max_behind()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Pattern, atomic};
+
+    #[test]
+    fn atomic_pattern() {
+        let greedy = (Pattern('a') * ..) & 'a';
+        assert!(greedy.test("aa"));
+
+        let possessive = atomic(Pattern('a') * ..) & 'a';
+        assert!(!possessive.test("a"));
+        assert!(!possessive.test("aa"));
+
+        let word = atomic(Pattern(char::is_alphabetic) * (1..)) & ';';
+        assert!(word.test("abc;"));
+        assert!(!word.test("abc"));
+
+        let first = atomic(Pattern("a") | "ab") & 'b';
+        assert!(first.test("ab"));
+        assert!(!first.test("abb"));
+    }
+}
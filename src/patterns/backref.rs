@@ -0,0 +1,120 @@
+
+use crate::haystack::Haystack;
+use crate::capture::{Capture, CaptureResult, CaptureFn, Context, GroupId};
+use crate::captures::{Captures, NoneCapture};
+
+/// Backreference to a capture group
+///
+/// Matches the same text as the last match of the group on the left of it.
+/// If the group has not matched yet, the backreference fails.
+/// Inside of `nocase` the text is compared ignoring case.
+///
+#[derive(Copy, Clone)]
+pub struct Backref(pub GroupId);
+
+impl<'a, H: ?Sized + Haystack> Capture<'a, H> for Backref {
+    type Inner = NoneCapture;
+
+    fn capture_each(
+        &self,
+        ctx: &mut Context<'a, H>,
+        text: &'a H,
+        f: &mut CaptureFn<'_, 'a, Self::Inner, H>,
+    ) -> bool {
+        let group = match ctx.group(self.0) {
+            Some(group) => group,
+            None => return false,
+        };
+
+        let nocase = ctx.nocase();
+        if group.scan_prefix(text, nocase).is_some_and(|len| len < group.len()) {
+            ctx.mark_end();
+        }
+
+        let scanned = text.scan_prefix(group, nocase);
+
+        match scanned {
+            Some(len) => {
+                let (left, rest) = text.split_at(len);
+                f(ctx, CaptureResult::new(Captures::without_capture(ctx.position(text), left), rest))
+            },
+            _ => false,
+        }
+    }
+
+    fn capture_empty(&self, ctx: &Context<'a, H>, text: &'a H) -> CaptureResult<'a, Self::Inner, H> {
+        CaptureResult::new(Captures::without_capture(ctx.position(text), text.split_at(0).0), text)
+    }
+
+    fn max_behind(&self) -> Option<usize> {
+        Some(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Pattern, cap, backref, atomic, longest, ahead};
+
+    #[test]
+    fn backref_pattern() {
+        let quote = cap(Pattern('\'') | '"');
+        let body = Pattern(|c: char| c != '\'' && c != '"') * ..;
+        let string = quote & body & backref(&quote);
+
+        assert!(string.test("'abc'"));
+        assert!(string.test("\"abc\""));
+        assert!(!string.test("'abc\""));
+        assert!(!string.test("\"abc'"));
+
+        let strings: Vec<&str> = string.matched_strs("'a' \"b\" 'c\"").collect();
+        assert_eq!(strings, ["'a'", "\"b\""]);
+    }
+
+    #[test]
+    fn doubled_words() {
+        // Patterns which are not `Copy` are moved into expression,
+        // so a backreference to them is created before
+        let word = cap(Pattern(char::is_alphabetic) * (1..));
+        let same_word = backref(&word);
+        let doubled = word & ' ' & same_word;
+
+        assert!(doubled.test("the the"));
+        assert!(!doubled.test("the then"));
+
+        let matches: Vec<&str> = doubled.matched_strs("it is is fine fine").collect();
+        assert_eq!(matches, ["is is", "fine fine"]);
+    }
+
+    #[test]
+    fn backref_backtracking() {
+        let group = cap(Pattern('a') * (1..));
+        let same = backref(&group);
+        let twice = group & same;
+        assert!(twice.test("aa"));
+        assert!(twice.test("aaaa"));
+        assert!(!twice.test("aaa"));
+
+        let unmatched = cap('x');
+        let either = (unmatched | 'y') & backref(&unmatched);
+        assert!(either.test("xx"));
+        assert!(!either.test("y"));
+        assert!(!either.test("yy"));
+    }
+
+    #[test]
+    fn backref_through_groups() {
+        let a = cap(Pattern('a') * (1..));
+        let same_a = backref(&a);
+        let atomic_twice = atomic(a) & '-' & same_a;
+        assert!(atomic_twice.test("aa-aa"));
+
+        let b = cap(Pattern('b') * (1..));
+        let same_b = backref(&b);
+        let longest_twice = longest('c', b) & '-' & same_b;
+        assert!(longest_twice.test("bb-bb"));
+
+        let c = cap('c');
+        let lookahead = ahead(c) & backref(&c);
+        assert!(!lookahead.test("c"));
+    }
+}
@@ -1,23 +1,87 @@
 
-use crate::capture::{Capture, CaptureResult};
+use crate::haystack::Haystack;
+use crate::capture::{Capture, CaptureResult, CaptureFn, Context, GroupId};
 use crate::captures::Captures;
 
+/// Capture group
+///
+/// Holds the pattern, the unique id of the group and the optional name of it.
+///
 #[derive(Copy, Clone)]
-pub struct Cap<S>(pub S);
+pub struct Cap<S>(pub S, pub GroupId, pub Option<&'static str>);
 
-impl<'a, S: Capture<'a>> Capture<'a> for Cap<S> {
-    type Inner = Captures<'a, S::Inner>;
+impl<'a, H: ?Sized + Haystack, S: Capture<'a, H>> Capture<'a, H> for Cap<S> {
+    type Inner = Captures<'a, S::Inner, H>;
 
-    fn capture(&self, text: &'a str) -> Option<CaptureResult<'a, Self::Inner>> {
-        let (caps, rest) = self.0.capture(text)?.into();
-        let caps_copy = caps.clone();
-        let inner = Captures::new(caps.captured_str, caps.inner);
-        Some(CaptureResult::new(caps_copy.with_inner(inner), rest))
+    fn capture_each(
+        &self,
+        ctx: &mut Context<'a, H>,
+        text: &'a H,
+        f: &mut CaptureFn<'_, 'a, Self::Inner, H>,
+    ) -> bool {
+        self.0.capture_each(ctx, text, &mut |ctx, res| {
+            let (caps, rest) = res.into();
+            let caps_copy = caps.clone();
+            let inner = Captures::new(caps.start, caps.captured_str, caps.inner).with_name(self.2);
+            let res = CaptureResult::new(caps_copy.with_inner(inner), rest);
+            ctx.with_group(self.1, res.captures.captured_str, |ctx| f(ctx, res))
+        })
     }
 
-    fn capture_empty(&self, text: &'a str) -> CaptureResult<'a, Self::Inner> {
-        let caps = self.0.capture_empty(text).captures;
-        let inner = Captures::new("", caps.clone().inner);
+    fn capture_empty(&self, ctx: &Context<'a, H>, text: &'a H) -> CaptureResult<'a, Self::Inner, H> {
+        let caps = self.0.capture_empty(ctx, text).captures;
+        let inner = Captures::new(caps.start, text.split_at(0).0, caps.clone().inner).with_name(self.2);
         CaptureResult::new(caps.with_inner(inner), text)
     }
+
+    fn max_width(&self) -> Option<usize> {
+        self.0.max_width()
+    }
+
+    fn max_behind(&self) -> Option<usize> {
+        self.0.max_behind()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Pattern, cap, cap_named};
+
+    #[test]
+    fn named_groups() {
+        let digit = Pattern(|c: char| c.is_ascii_digit());
+        let date = cap_named("year", digit * 4)
+            & '-' & cap_named("month", digit * 2)
+            & '-' & cap_named("day", digit * 2);
+
+        let caps = date.matches("on 2020-05-17.").next().unwrap();
+        assert_eq!(caps.name("year"), Some("2020"));
+        assert_eq!(caps.name("month"), Some("05"));
+        assert_eq!(caps.name("day"), Some("17"));
+        assert_eq!(caps.name("hour"), None);
+
+        let captures: Vec<&str> = caps.collect();
+        assert_eq!(captures, ["2020-05-17", "2020", "05", "17"]);
+    }
+
+    #[test]
+    fn named_groups_nested() {
+        let pair = || {
+            let word = Pattern(char::is_alphabetic);
+            let number = Pattern(char::is_numeric);
+            let key = cap_named("key", word * (1..));
+            let value = cap_named("value", cap(word * (1..)) | cap_named("number", number * (1..)));
+            cap_named("pair", key & '=' & value)
+        };
+
+        let caps = pair().matches("a=b").next().unwrap();
+        assert_eq!(caps.name("pair"), Some("a=b"));
+        assert_eq!(caps.name("key"), Some("a"));
+        assert_eq!(caps.name("value"), Some("b"));
+        assert_eq!(caps.name("number"), None);
+
+        let caps = pair().matches("x=12").next().unwrap();
+        assert_eq!(caps.name("value"), Some("12"));
+        assert_eq!(caps.name("number"), Some("12"));
+    }
 }
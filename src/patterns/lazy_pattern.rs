@@ -0,0 +1,119 @@
+
+use std::ops::{RangeBounds, Bound};
+use crate::haystack::Haystack;
+use crate::capture::{CaptureResult, Capture, CaptureFn, Context};
+use crate::patterns::{Repeat, RepeatFn, repeat, repeat_width};
+
+/// Result of lazy (non-greedy) range pattern
+///
+/// Unlike `RangePattern` it tries the minimum number of repetitions first
+/// and repeats the pattern once more only when the rest of expression fails.
+///
+/// # Examples
+/// ```
+/// # use rep::{Pattern, ScanTerm};
+/// let tag = Pattern("<b>") & (Pattern(|_: char| true) * ..).lazy() & "</b>";
+/// let tags: Vec<&str> = tag.matched_strs("<b>x</b> and <b>y</b>").collect();
+/// assert_eq!(tags, ["<b>x</b>", "<b>y</b>"]);
+/// ```
+///
+#[derive(Copy, Clone)]
+pub struct LazyPattern<S, R>(pub S, pub R);
+
+impl<'a, H: ?Sized + Haystack, S: Capture<'a, H>, R: RangeBounds<u32>> Repeat<'a, H> for LazyPattern<S, R> {
+    fn repeat_each(
+        &self,
+        ctx: &mut Context<'a, H>,
+        text: &'a H,
+        f: &mut RepeatFn<'_, 'a, Self::Inner, H>,
+    ) -> bool {
+
+        let empty = text.split_at(0).0;
+        if self.0.capture(&mut Context::new(empty), empty).is_some() && self.1.end_bound() == Bound::Unbounded {
+            panic!("Infinity loop")
+        }
+
+        repeat(&self.0, &self.1, false, ctx, text, f)
+    }
+}
+
+impl<'a, H: ?Sized + Haystack, S: Capture<'a, H>, R: RangeBounds<u32>> Capture<'a, H> for LazyPattern<S, R> {
+    type Inner = S::Inner;
+
+    fn capture_each(
+        &self,
+        ctx: &mut Context<'a, H>,
+        text: &'a H,
+        f: &mut CaptureFn<'_, 'a, Self::Inner, H>,
+    ) -> bool {
+        self.repeat_each(ctx, text, &mut |ctx, res, _| f(ctx, res))
+    }
+
+    fn capture_empty(&self, ctx: &Context<'a, H>, text: &'a H) -> CaptureResult<'a, Self::Inner, H> {
+        self.0.capture_empty(ctx, text)
+    }
+
+    fn max_width(&self) -> Option<usize> {
+        repeat_width(self.0.max_width()?, &self.1)
+    }
+
+    fn max_behind(&self) -> Option<usize> {
+        self.0.max_behind()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Pattern, end};
+
+    #[test]
+    fn lazy_pattern() {
+        let full = (Pattern("a") * ..).lazy();
+        assert!(full.test(""));
+        assert!(full.test("a"));
+        assert!(full.test("aaa"));
+
+        let range = (Pattern("b") * (1..3)).lazy();
+        assert!(!range.test(""));
+        assert!(range.test("b"));
+        assert!(range.test("bb"));
+        assert!(!range.test("bbb"));
+
+        let exact = (Pattern("c") * 2).lazy();
+        assert!(!exact.test("c"));
+        assert!(exact.test("cc"));
+        assert!(!exact.test("ccc"));
+    }
+
+    #[test]
+    fn lazy_pattern_shortest() {
+        let any = Pattern(|_: char| true);
+
+        let greedy = Pattern('"') & (any * ..) & '"';
+        let greedy_matches: Vec<&str> = greedy.matched_strs(r#""a" "b""#).collect();
+        assert_eq!(greedy_matches, [r#""a" "b""#]);
+
+        let lazy = Pattern('"') & (any * ..).lazy() & '"';
+        let lazy_matches: Vec<&str> = lazy.matched_strs(r#""a" "b""#).collect();
+        assert_eq!(lazy_matches, [r#""a""#, r#""b""#]);
+
+        let from = (Pattern('x') * (2..)).lazy();
+        let from_matches: Vec<&str> = from.matched_strs("xxxxx").collect();
+        assert_eq!(from_matches, ["xx", "xx"]);
+    }
+
+    #[test]
+    fn lazy_pattern_long_input() {
+        let text = "ab".repeat(50_000);
+        let pairs = ((Pattern('a') | "ab") * (1..)).lazy() & end();
+        assert!(pairs.test(&text));
+        assert!(!pairs.test(&(text + "c")));
+    }
+
+    #[test]
+    #[should_panic(expected = "Infinity loop")]
+    fn lazy_infinity_loop_panic() {
+        let empty_pattern = (Pattern("") * (1..)).lazy();
+        empty_pattern.test("a");
+    }
+}
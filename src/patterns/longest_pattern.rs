@@ -0,0 +1,166 @@
+
+use crate::haystack::Haystack;
+use crate::capture::{Capture, CaptureResult, CaptureFn, Context};
+use crate::captures::{EitherCaptures, Captures};
+
+/// Leftmost-longest alternation of patterns
+///
+/// Unlike `OrPattern` it tries both branches
+/// and prefers the match which consumes the most input.
+/// On equal lengths the left branch wins.
+///
+/// Matches are passed on from the longest to the shortest without keeping them.
+/// Both branches are matched again for every length the rest of expression rejects,
+/// so it suits branches with a few lengths of matches, like tokens.
+///
+#[derive(Copy, Clone)]
+pub struct LongestPattern<A, B>(pub A, pub B);
+
+impl<'a, H: ?Sized + Haystack, A: Capture<'a, H>, B: Capture<'a, H>> Capture<'a, H> for LongestPattern<A, B> {
+    type Inner = EitherCaptures<A::Inner, B::Inner>;
+
+    fn capture_each(
+        &self,
+        ctx: &mut Context<'a, H>,
+        text: &'a H,
+        f: &mut CaptureFn<'_, 'a, Self::Inner, H>,
+    ) -> bool {
+        // Every pass finds the longest length of matches shorter than the ones already tried
+        // and passes the matches of that length to `f`, so no match is kept in memory
+        let mut shorter_than = None;
+
+        loop {
+            let mut longest = None;
+            let mut longest_of = |len: usize| {
+                if shorter_than.is_none_or(|bound| len < bound) {
+                    longest = longest.max(Some(len));
+                }
+            };
+
+            self.0.capture_each(ctx, text, &mut |_, res| {
+                longest_of(res.captures.captured_len());
+                false
+            });
+
+            self.1.capture_each(ctx, text, &mut |_, res| {
+                longest_of(res.captures.captured_len());
+                false
+            });
+
+            let len = match longest {
+                Some(len) => len,
+                None => return false,
+            };
+
+            // The left branch keeps priority on equal lengths
+            let found = self.0.capture_each(ctx, text, &mut |ctx, res| {
+                if res.captures.captured_len() != len {
+                    return false;
+                }
+
+                let inner = EitherCaptures::Left(res.captures.inner);
+                let caps = Captures::new(res.captures.start, res.captures.captured_str, inner);
+                f(ctx, CaptureResult::new(caps, res.rest))
+            });
+
+            let found = found || self.1.capture_each(ctx, text, &mut |ctx, res| {
+                if res.captures.captured_len() != len {
+                    return false;
+                }
+
+                let inner = EitherCaptures::Right(res.captures.inner);
+                let caps = Captures::new(res.captures.start, res.captures.captured_str, inner);
+                f(ctx, CaptureResult::new(caps, res.rest))
+            });
+
+            if found {
+                return true;
+            }
+
+            shorter_than = Some(len);
+        }
+    }
+
+    fn capture_empty(&self, ctx: &Context<'a, H>, text: &'a H) -> CaptureResult<'a, Self::Inner, H> {
+        let inner = EitherCaptures::Left(self.0.capture_empty(ctx, text).captures.inner);
+        let caps = Captures::new(ctx.position(text), text.split_at(0).0, inner);
+        CaptureResult::new(caps, text)
+    }
+
+    fn max_width(&self) -> Option<usize> {
+        Some(self.0.max_width()?.max(self.1.max_width()?))
+    }
+
+    fn max_behind(&self) -> Option<usize> {
+        Some(self.0.max_behind()?.max(self.1.max_behind()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Pattern, longest, cap, backref, end};
+
+    #[test]
+    fn longest_pattern() {
+        let first = Pattern("a") | "ab";
+        let first_matches: Vec<&str> = first.matched_strs("ab").collect();
+        assert_eq!(first_matches, ["a"]);
+
+        let pattern = longest("a", "ab");
+        let matches: Vec<&str> = pattern.matched_strs("ab").collect();
+        assert_eq!(matches, ["ab"]);
+        assert!(pattern.test("a"));
+        assert!(pattern.test("ab"));
+        assert!(!pattern.test("b"));
+
+        let many = longest(longest("x", "xyz"), "xy");
+        let many_matches: Vec<&str> = many.matched_strs("xy xyz x").collect();
+        assert_eq!(many_matches, ["xy", "xyz", "x"]);
+    }
+
+    #[test]
+    fn longest_pattern_backtracking() {
+        let pattern = longest("a", "ab") & 'b';
+        assert!(pattern.test("ab"));
+        assert!(pattern.test("abb"));
+    }
+
+    #[test]
+    fn longest_pattern_tries_every_length() {
+        let letter = cap(Pattern(char::is_alphabetic));
+        let pattern = longest(letter & (Pattern('a') * ..), Pattern("ba") | "b") & backref(&letter) & end();
+
+        assert!(pattern.test("baaab"));
+        assert!(pattern.test("bab"));
+        assert!(pattern.test("bb"));
+        assert!(!pattern.test("bac"));
+
+        let lengths = longest(Pattern('x') * .., "xx") & 'x' & end();
+        let found = lengths.matches("xxxx").next().unwrap();
+        assert_eq!(found.captured_str, "xxxx");
+    }
+
+    #[test]
+    fn keyword_or_identifier() {
+        let alpha = Pattern(char::is_alphabetic);
+        let keyword = Pattern("if") | "else";
+        let identifier = alpha & (alpha * ..);
+        let token = longest(keyword, identifier);
+
+        let mut tokens = token.matches("if iffy else");
+
+        let cap = tokens.next().unwrap();
+        assert_eq!(cap.captured_str, "if");
+        assert!(matches!(cap.inner, super::EitherCaptures::Left(_)));
+
+        let cap = tokens.next().unwrap();
+        assert_eq!(cap.captured_str, "iffy");
+        assert!(matches!(cap.inner, super::EitherCaptures::Right(_)));
+
+        let cap = tokens.next().unwrap();
+        assert_eq!(cap.captured_str, "else");
+        assert!(matches!(cap.inner, super::EitherCaptures::Left(_)));
+
+        assert!(tokens.next().is_none());
+    }
+}
@@ -0,0 +1,251 @@
+
+use std::iter;
+use crate::haystack::Haystack;
+use crate::capture::{Capture, CaptureResult, CaptureFn, Context};
+use crate::captures::{Captures, NoneCapture};
+
+/// Positive lookahead
+///
+/// Zero-width assertion which matches if the pattern matches after the current position.
+///
+#[derive(Copy, Clone)]
+pub struct Ahead<S>(pub S);
+
+/// Negative lookahead
+///
+/// Zero-width assertion which matches if the pattern doesn't match after the current position.
+///
+#[derive(Copy, Clone)]
+pub struct NotAhead<S>(pub S);
+
+/// Positive lookbehind
+///
+/// Zero-width assertion which matches if the pattern matches text
+/// which ends exactly at the current position.
+///
+/// Only positions within the maximum width of the pattern are tried.
+/// A pattern of unbounded width (like `x * ..`) is tried from every earlier position,
+/// so a search with it takes quadratic time.
+///
+#[derive(Copy, Clone)]
+pub struct Behind<S>(pub S);
+
+/// Negative lookbehind
+///
+/// Zero-width assertion which matches if no text
+/// which ends at the current position matches the pattern.
+/// Like `Behind` it's fast for patterns of bounded width only.
+///
+#[derive(Copy, Clone)]
+pub struct NotBehind<S>(pub S);
+
+fn is_ahead<'a, H, S>(pattern: &S, ctx: &mut Context<'a, H>, text: &'a H) -> bool where
+    H: ?Sized + Haystack,
+    S: Capture<'a, H>,
+{
+    pattern.capture(ctx, text).is_some()
+}
+
+fn is_behind<'a, H, S>(pattern: &S, ctx: &mut Context<'a, H>, text: &'a H) -> bool where
+    H: ?Sized + Haystack,
+    S: Capture<'a, H>,
+{
+    let before = ctx.before(text);
+
+    // Try the nearest positions first, so short lookbehinds fail fast,
+    // and never look further than the widest match of the pattern
+    let width = pattern.max_width().map_or(usize::MAX, |width| width.saturating_add(1));
+    let starts = iter::successors(Some(before.len()), |&pos| before.prev_pos(pos)).take(width);
+
+    for start in starts {
+        let from = ctx.rest_at(start);
+        if pattern.capture_each(ctx, from, &mut |_, res| res.rest.len() == text.len()) {
+            return true;
+        }
+    }
+
+    false
+}
+
+fn assertion<'a, H: ?Sized + Haystack>(
+    matched: bool,
+    ctx: &mut Context<'a, H>,
+    text: &'a H,
+    f: &mut CaptureFn<'_, 'a, NoneCapture, H>,
+) -> bool {
+    if !matched {
+        return false;
+    }
+
+    let res = empty(ctx, text);
+    f(ctx, res)
+}
+
+fn empty<'a, H: ?Sized + Haystack>(ctx: &Context<'a, H>, text: &'a H) -> CaptureResult<'a, NoneCapture, H> {
+    CaptureResult::new(Captures::without_capture(ctx.position(text), text.split_at(0).0), text)
+}
+
+impl<'a, H: ?Sized + Haystack, S: Capture<'a, H>> Capture<'a, H> for Ahead<S> {
+    type Inner = NoneCapture;
+
+    fn capture_each(
+        &self,
+        ctx: &mut Context<'a, H>,
+        text: &'a H,
+        f: &mut CaptureFn<'_, 'a, Self::Inner, H>,
+    ) -> bool {
+        assertion(is_ahead(&self.0, ctx, text), ctx, text, f)
+    }
+
+    fn capture_empty(&self, ctx: &Context<'a, H>, text: &'a H) -> CaptureResult<'a, Self::Inner, H> {
+        empty(ctx, text)
+    }
+
+    fn max_width(&self) -> Option<usize> {
+        Some(0)
+    }
+
+    fn max_behind(&self) -> Option<usize> {
+        self.0.max_behind()
+    }
+}
+
+impl<'a, H: ?Sized + Haystack, S: Capture<'a, H>> Capture<'a, H> for NotAhead<S> {
+    type Inner = NoneCapture;
+
+    fn capture_each(
+        &self,
+        ctx: &mut Context<'a, H>,
+        text: &'a H,
+        f: &mut CaptureFn<'_, 'a, Self::Inner, H>,
+    ) -> bool {
+        assertion(!is_ahead(&self.0, ctx, text), ctx, text, f)
+    }
+
+    fn capture_empty(&self, ctx: &Context<'a, H>, text: &'a H) -> CaptureResult<'a, Self::Inner, H> {
+        empty(ctx, text)
+    }
+
+    fn max_width(&self) -> Option<usize> {
+        Some(0)
+    }
+
+    fn max_behind(&self) -> Option<usize> {
+        self.0.max_behind()
+    }
+}
+
+impl<'a, H: ?Sized + Haystack, S: Capture<'a, H>> Capture<'a, H> for Behind<S> {
+    type Inner = NoneCapture;
+
+    fn capture_each(
+        &self,
+        ctx: &mut Context<'a, H>,
+        text: &'a H,
+        f: &mut CaptureFn<'_, 'a, Self::Inner, H>,
+    ) -> bool {
+        assertion(is_behind(&self.0, ctx, text), ctx, text, f)
+    }
+
+    fn capture_empty(&self, ctx: &Context<'a, H>, text: &'a H) -> CaptureResult<'a, Self::Inner, H> {
+        empty(ctx, text)
+    }
+
+    fn max_width(&self) -> Option<usize> {
+        Some(0)
+    }
+
+    fn max_behind(&self) -> Option<usize> {
+        self.0.max_width()?.checked_add(self.0.max_behind()?)
+    }
+}
+
+impl<'a, H: ?Sized + Haystack, S: Capture<'a, H>> Capture<'a, H> for NotBehind<S> {
+    type Inner = NoneCapture;
+
+    fn capture_each(
+        &self,
+        ctx: &mut Context<'a, H>,
+        text: &'a H,
+        f: &mut CaptureFn<'_, 'a, Self::Inner, H>,
+    ) -> bool {
+        assertion(!is_behind(&self.0, ctx, text), ctx, text, f)
+    }
+
+    fn capture_empty(&self, ctx: &Context<'a, H>, text: &'a H) -> CaptureResult<'a, Self::Inner, H> {
+        empty(ctx, text)
+    }
+
+    fn max_width(&self) -> Option<usize> {
+        Some(0)
+    }
+
+    fn max_behind(&self) -> Option<usize> {
+        self.0.max_width()?.checked_add(self.0.max_behind()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Pattern, ahead, not_ahead, behind, not_behind, cap};
+
+    #[test]
+    fn lookahead() {
+        let alpha = Pattern(char::is_alphabetic);
+
+        let call = (alpha * (1..)) & ahead('(');
+        assert!(!call.test("foo"));
+        let calls: Vec<&str> = call.matched_strs("foo(bar) baz(").collect();
+        assert_eq!(calls, ["foo", "baz"]);
+
+        let name = (alpha * (1..)) & not_ahead(Pattern('(') | char::is_alphabetic);
+        let names: Vec<&str> = name.matched_strs("foo(bar) baz(").collect();
+        assert_eq!(names, ["bar"]);
+    }
+
+    #[test]
+    fn lookbehind() {
+        let digit = Pattern(|c: char| c.is_ascii_digit());
+
+        let fraction = behind('.') & (digit * (1..));
+        let fractions: Vec<&str> = fraction.matched_strs("1.25 3 .5").collect();
+        assert_eq!(fractions, ["25", "5"]);
+
+        let integer = not_behind(Pattern('.') | digit) & (digit * (1..));
+        let integers: Vec<&str> = integer.matched_strs("1.25 3 .5").collect();
+        assert_eq!(integers, ["1", "3"]);
+
+        let after_word = (Pattern("фы") * ..) & behind("фы") & 'x';
+        assert!(after_word.test("фыфыx"));
+        assert!(!after_word.test("x"));
+
+        let bounded = behind(Pattern("ab") | (Pattern('c') * (1..=3))) & 'x';
+        let found: Vec<usize> = bounded.match_indices("abx cx cccx ccccx x").map(|(pos, _)| pos).collect();
+        assert_eq!(found, [2, 5, 10, 16]);
+    }
+
+    #[test]
+    fn lookbehind_long_input() {
+        let text = "a".repeat(50_000) + "xa";
+
+        let not_after_x = not_behind('x') & 'a';
+        assert_eq!(not_after_x.matches(&text).count(), 50_000);
+
+        let after_word = behind((Pattern(char::is_alphabetic) * (1..=3)) & ' ') & 'a';
+        assert_eq!(after_word.matches(&text).count(), 0);
+    }
+
+    #[test]
+    fn lookaround_zero_width() {
+        let pattern = Pattern('a') & ahead('b') & behind('a') & 'b';
+        assert!(pattern.test("ab"));
+        assert!(!pattern.test("a"));
+
+        let captures: Vec<&str> = (cap('a') & not_ahead('c') & cap('b'))
+            .matches("acab")
+            .next()
+            .unwrap()
+            .collect();
+        assert_eq!(captures, ["ab", "a", "b"]);
+    }
+}
@@ -1,5 +1,7 @@
 
-use crate::capture::{CaptureResult, Capture};
+use crate::haystack::Haystack;
+use crate::capture::{CaptureResult, Capture, CaptureFn, Context};
+use crate::patterns::{Repeat, RepeatFn, repeat};
 
 /// Result of pattern repetition
 ///
@@ -13,24 +15,39 @@ use crate::capture::{CaptureResult, Capture};
 #[derive(Copy, Clone)]
 pub struct ManyPattern<S>(pub S, pub u32);
 
-impl<'a, S: Capture<'a>> Capture<'a> for ManyPattern<S> {
+impl<'a, H: ?Sized + Haystack, S: Capture<'a, H>> Repeat<'a, H> for ManyPattern<S> {
+    fn repeat_each(
+        &self,
+        ctx: &mut Context<'a, H>,
+        text: &'a H,
+        f: &mut RepeatFn<'_, 'a, Self::Inner, H>,
+    ) -> bool {
+        repeat(&self.0, &(self.1..=self.1), true, ctx, text, f)
+    }
+}
+
+impl<'a, H: ?Sized + Haystack, S: Capture<'a, H>> Capture<'a, H> for ManyPattern<S> {
     type Inner = S::Inner;
 
-    fn capture(&self, text: &'a str) -> Option<CaptureResult<'a, Self::Inner>> {
-        let mut cap = self.0.capture_empty(text);
-        let mut len = 0;
+    fn capture_each(
+        &self,
+        ctx: &mut Context<'a, H>,
+        text: &'a H,
+        f: &mut CaptureFn<'_, 'a, Self::Inner, H>,
+    ) -> bool {
+        self.repeat_each(ctx, text, &mut |ctx, res, _| f(ctx, res))
+    }
 
-        for _ in 0..self.1 {
-            cap = self.0.capture(cap.rest)?;
-            len += cap.captures.captured_len();
-            cap.captures.captured_str = &text[..len];
-        }
+    fn capture_empty(&self, ctx: &Context<'a, H>, text: &'a H) -> CaptureResult<'a, Self::Inner, H> {
+        self.0.capture_empty(ctx, text)
+    }
 
-        Some(cap)
+    fn max_width(&self) -> Option<usize> {
+        self.0.max_width()?.checked_mul(self.1 as usize)
     }
 
-    fn capture_empty(&self, text: &'a str) -> CaptureResult<'a, Self::Inner> {
-        self.0.capture_empty(text)
+    fn max_behind(&self) -> Option<usize> {
+        self.0.max_behind()
     }
 }
 
@@ -59,4 +76,19 @@ mod tests {
         assert!(pattern.test("zzzz"));
         assert!(!pattern.test("zzzzz"));
     }
+
+    #[test]
+    fn many_pattern_backtracking() {
+        let pattern = ((Pattern("a") | "ab") * 2) & 'b';
+        assert!(pattern.test("aab"));
+        assert!(pattern.test("abab"));
+        assert!(!pattern.test("abb"));
+    }
+
+    #[test]
+    fn many_pattern_long_input() {
+        let text = "ab".repeat(50_000);
+        assert!(((Pattern('a') | "ab") * 50_000).test(&text));
+        assert!(!((Pattern('a') | "ab") * 50_001).test(&text));
+    }
 }
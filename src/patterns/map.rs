@@ -0,0 +1,205 @@
+
+use crate::haystack::Haystack;
+use crate::capture::{Capture, CaptureResult, CaptureFn, Context};
+use crate::captures::{Mapped, Output};
+
+/// Pattern which transforms its value with a function
+///
+/// The function receives the value of the inner pattern,
+/// which is the matched text unless the pattern is mapped already.
+///
+#[derive(Copy, Clone)]
+pub struct Map<S, F>(pub S, pub F);
+
+/// Pattern which transforms its value with a fallible function
+///
+/// The match is rejected if the function returns `None`,
+/// so the expression backtracks to the next match.
+///
+#[derive(Copy, Clone)]
+pub struct AndThen<S, F>(pub S, pub F);
+
+/// Pattern which accepts only matches with a value satisfying the predicate
+///
+/// Rejected matches are backtracked like failed ones.
+///
+#[derive(Copy, Clone)]
+pub struct Verify<S, F>(pub S, pub F);
+
+impl<'a, H: ?Sized + Haystack, S, F, T> Capture<'a, H> for Map<S, F> where
+    S: Capture<'a, H>,
+    S::Inner: Output<'a, H>,
+    F: Fn(<S::Inner as Output<'a, H>>::Value) -> T,
+    T: Clone,
+{
+    type Inner = Mapped<T>;
+
+    fn capture_each(
+        &self,
+        ctx: &mut Context<'a, H>,
+        text: &'a H,
+        f: &mut CaptureFn<'_, 'a, Self::Inner, H>,
+    ) -> bool {
+        self.0.capture_each(ctx, text, &mut |ctx, res| {
+            let inner = match res.captures.value() {
+                Some(value) => Mapped::new((self.1)(value)),
+                None => Mapped::empty(),
+            };
+
+            let caps = res.captures.with_inner(inner);
+            f(ctx, CaptureResult::new(caps, res.rest))
+        })
+    }
+
+    fn capture_empty(&self, ctx: &Context<'a, H>, text: &'a H) -> CaptureResult<'a, Self::Inner, H> {
+        let caps = self.0.capture_empty(ctx, text).captures;
+        CaptureResult::new(caps.with_inner(Mapped::empty()), text)
+    }
+
+    fn max_width(&self) -> Option<usize> {
+        self.0.max_width()
+    }
+
+    fn max_behind(&self) -> Option<usize> {
+        self.0.max_behind()
+    }
+}
+
+impl<'a, H: ?Sized + Haystack, S, F, T> Capture<'a, H> for AndThen<S, F> where
+    S: Capture<'a, H>,
+    S::Inner: Output<'a, H>,
+    F: Fn(<S::Inner as Output<'a, H>>::Value) -> Option<T>,
+    T: Clone,
+{
+    type Inner = Mapped<T>;
+
+    fn capture_each(
+        &self,
+        ctx: &mut Context<'a, H>,
+        text: &'a H,
+        f: &mut CaptureFn<'_, 'a, Self::Inner, H>,
+    ) -> bool {
+        self.0.capture_each(ctx, text, &mut |ctx, res| {
+            let inner = match res.captures.value() {
+                Some(value) => match (self.1)(value) {
+                    Some(value) => Mapped::new(value),
+                    None => return false,
+                },
+                None => Mapped::empty(),
+            };
+
+            let caps = res.captures.with_inner(inner);
+            f(ctx, CaptureResult::new(caps, res.rest))
+        })
+    }
+
+    fn capture_empty(&self, ctx: &Context<'a, H>, text: &'a H) -> CaptureResult<'a, Self::Inner, H> {
+        let caps = self.0.capture_empty(ctx, text).captures;
+        CaptureResult::new(caps.with_inner(Mapped::empty()), text)
+    }
+
+    fn max_width(&self) -> Option<usize> {
+        self.0.max_width()
+    }
+
+    fn max_behind(&self) -> Option<usize> {
+        self.0.max_behind()
+    }
+}
+
+impl<'a, H: ?Sized + Haystack, S, F> Capture<'a, H> for Verify<S, F> where
+    S: Capture<'a, H>,
+    S::Inner: Output<'a, H>,
+    F: Fn(&<S::Inner as Output<'a, H>>::Value) -> bool,
+{
+    type Inner = S::Inner;
+
+    fn capture_each(
+        &self,
+        ctx: &mut Context<'a, H>,
+        text: &'a H,
+        f: &mut CaptureFn<'_, 'a, Self::Inner, H>,
+    ) -> bool {
+        self.0.capture_each(ctx, text, &mut |ctx, res| {
+            let verified = res.captures
+                .value()
+                .is_none_or(|value| (self.1)(&value));
+
+            verified && f(ctx, res)
+        })
+    }
+
+    fn capture_empty(&self, ctx: &Context<'a, H>, text: &'a H) -> CaptureResult<'a, Self::Inner, H> {
+        self.0.capture_empty(ctx, text)
+    }
+
+    fn max_width(&self) -> Option<usize> {
+        self.0.max_width()
+    }
+
+    fn max_behind(&self) -> Option<usize> {
+        self.0.max_behind()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Pattern, cap};
+
+    #[test]
+    fn map_pattern() {
+        let number = (Pattern(|c: char| c.is_ascii_digit()) * (1..)).map(|s| s.parse::<u32>().unwrap());
+        assert_eq!(number.parse("123"), Some(123));
+        assert_eq!(number.parse("12x"), None);
+
+        let values: Vec<Option<u32>> = number.clone().matches("1 22 333").map(|caps| caps.value()).collect();
+        assert_eq!(values, [Some(1), Some(22), Some(333)]);
+
+        let double = number.clone().map(|n| n * 2);
+        assert_eq!(double.parse("21"), Some(42));
+
+        let grouped = cap(number);
+        assert_eq!(grouped.parse("7"), Some(7));
+    }
+
+    #[test]
+    fn alternation_value() {
+        let digit = Pattern(|c: char| c.is_ascii_digit());
+        let number = (digit * (1..)).map(|s| s.parse::<i64>().unwrap());
+        let boolean = Pattern("true").map(|_| 1) | Pattern("false").map(|_| 0);
+        let value = number | boolean;
+
+        assert_eq!(value.parse("15"), Some(15));
+        assert_eq!(value.parse("true"), Some(1));
+        assert_eq!(value.parse("false"), Some(0));
+        assert_eq!(value.parse("maybe"), None);
+    }
+
+    #[test]
+    fn and_then_pattern() {
+        let digit = Pattern(|c: char| c.is_ascii_digit());
+        let byte = (digit * (1..=3)).and_then(|s| s.parse::<u8>().ok());
+        assert_eq!(byte.parse("255"), Some(255));
+        assert_eq!(byte.parse("256"), None);
+
+        // The rejected match is backtracked to a shorter one
+        let octets: Vec<Option<u8>> = byte.matches("300").map(|caps| caps.value()).collect();
+        assert_eq!(octets, [Some(30), Some(0)]);
+    }
+
+    #[test]
+    fn verify_pattern() {
+        let digit = Pattern(|c: char| c.is_ascii_digit());
+        let small = (digit * (1..)).map(|s| s.parse::<u32>().unwrap()).verify(|n| *n < 256);
+        assert_eq!(small.parse("200"), Some(200));
+        assert_eq!(small.parse("300"), None);
+
+        let ip = small.clone() & '.' & small.clone() & '.' & small.clone() & '.' & small.clone();
+        assert!(ip.test("192.168.0.1"));
+        assert!(!ip.test("192.168.0.256"));
+
+        let even = (digit * (1..)).verify(|s| s.len() % 2 == 0);
+        let matches: Vec<&str> = even.matched_strs("1 22 333 4444").collect();
+        assert_eq!(matches, ["22", "33", "4444"]);
+    }
+}
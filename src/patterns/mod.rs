@@ -1,14 +1,52 @@
 
 mod pattern;
 mod cap;
+mod backref;
+mod atomic;
 mod and_pattern;
 mod or_pattern;
+mod longest_pattern;
 mod many_pattern;
 mod range_pattern;
+mod lookaround;
+mod anchor;
+mod lazy_pattern;
+mod repeated;
+mod map;
+mod nocase;
 
-pub use pattern::{Pattern, cap};
+pub use pattern::{
+    Pattern,
+    cap,
+    cap_named,
+    backref,
+    atomic,
+    longest,
+    ahead,
+    not_ahead,
+    behind,
+    not_behind,
+    start,
+    end,
+    line_start,
+    line_end,
+    word_boundary,
+    not_word_boundary,
+    nocase,
+    one_of,
+    none_of,
+};
 pub use cap::Cap;
+pub use backref::Backref;
+pub use atomic::Atomic;
 pub use and_pattern::AndPattern;
 pub use or_pattern::OrPattern;
+pub use longest_pattern::LongestPattern;
 pub use many_pattern::ManyPattern;
 pub use range_pattern::RangePattern;
+pub use lazy_pattern::LazyPattern;
+pub use repeated::{Repeated, Repeat, RepeatFn, repeat, repeat_width};
+pub use lookaround::{Ahead, NotAhead, Behind, NotBehind};
+pub use anchor::Anchor;
+pub use map::{Map, AndThen, Verify};
+pub use nocase::NoCase;
@@ -0,0 +1,96 @@
+
+use crate::haystack::Haystack;
+use crate::capture::{Capture, CaptureResult, CaptureFn, Context};
+
+/// Case-insensitive pattern
+///
+/// Every term of the inner pattern is matched ignoring case.
+/// The rest of expression keeps its own mode.
+///
+#[derive(Copy, Clone)]
+pub struct NoCase<S>(pub S);
+
+impl<'a, H: ?Sized + Haystack, S: Capture<'a, H>> Capture<'a, H> for NoCase<S> {
+    type Inner = S::Inner;
+
+    fn capture_each(
+        &self,
+        ctx: &mut Context<'a, H>,
+        text: &'a H,
+        f: &mut CaptureFn<'_, 'a, Self::Inner, H>,
+    ) -> bool {
+        let outer = ctx.nocase();
+        ctx.with_nocase(true, |ctx| {
+            self.0.capture_each(ctx, text, &mut |ctx, res| {
+                ctx.with_nocase(outer, |ctx| f(ctx, res))
+            })
+        })
+    }
+
+    fn capture_empty(&self, ctx: &Context<'a, H>, text: &'a H) -> CaptureResult<'a, Self::Inner, H> {
+        self.0.capture_empty(ctx, text)
+    }
+
+    fn is_simple(&self) -> bool {
+        self.0.is_simple()
+    }
+
+    fn max_width(&self) -> Option<usize> {
+        self.0.max_width()
+    }
+
+    fn max_behind(&self) -> Option<usize> {
+        self.0.max_behind()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Pattern, nocase, cap, backref};
+
+    #[test]
+    fn nocase_pattern() {
+        let select = nocase("select") & ' ' & nocase('*');
+        assert!(select.test("select *"));
+        assert!(select.test("SELECT *"));
+        assert!(select.test("SeLeCt *"));
+        assert!(!select.test("SELEC *"));
+
+        let keyword = nocase(Pattern("if") | "else");
+        let keywords: Vec<&str> = keyword.matched_strs("If ELSE iF").collect();
+        assert_eq!(keywords, ["If", "ELSE", "iF"]);
+
+        let hex = Pattern("0x") & (nocase(Pattern('a'..='f') | ('0'..='9')) * (1..));
+        assert!(hex.test("0xFFaa09"));
+        assert!(!hex.test("0XFF"));
+        assert!(!hex.test("0xFG"));
+    }
+
+    #[test]
+    fn nocase_scope() {
+        let pattern = nocase("a") & "b";
+        assert!(pattern.test("Ab"));
+        assert!(!pattern.test("AB"));
+
+        let nested = nocase(Pattern("a") & "b") & 'c';
+        assert!(nested.test("ABc"));
+        assert!(!nested.test("ABC"));
+    }
+
+    #[test]
+    fn nocase_byte_lengths() {
+        // Kelvin sign and long s are longer than their ASCII folds
+        let pattern = nocase("kiss");
+        let matches: Vec<&str> = pattern.matched_strs("\u{212A}i\u{17F}s KISS").collect();
+        assert_eq!(matches, ["\u{212A}i\u{17F}s", "KISS"]);
+    }
+
+    #[test]
+    fn nocase_backref() {
+        let word = cap(Pattern(char::is_alphabetic) * (1..));
+        let same = backref(&word);
+        let pattern = nocase(word & ' ' & same);
+        assert!(pattern.test("Hello hello"));
+        assert!(!pattern.test("Hello world"));
+    }
+}
@@ -1,33 +1,46 @@
 
-use crate::capture::{Capture, CaptureResult};
+use crate::haystack::Haystack;
+use crate::capture::{Capture, CaptureResult, CaptureFn, Context};
 use crate::captures::{EitherCaptures, Captures};
 
 #[derive(Copy, Clone)]
 pub struct OrPattern<A, B>(pub A, pub B);
 
-impl<'a, A: Capture<'a>, B: Capture<'a>> Capture<'a> for OrPattern<A, B> {
+impl<'a, H: ?Sized + Haystack, A: Capture<'a, H>, B: Capture<'a, H>> Capture<'a, H> for OrPattern<A, B> {
     type Inner = EitherCaptures<A::Inner, B::Inner>;
 
-    fn capture(&self, text: &'a str) -> Option<CaptureResult<'a, Self::Inner>> {
-        let (caps, rest) = if let Some(res) = self.0.capture(text) {
+    fn capture_each(
+        &self,
+        ctx: &mut Context<'a, H>,
+        text: &'a H,
+        f: &mut CaptureFn<'_, 'a, Self::Inner, H>,
+    ) -> bool {
+        let left = self.0.capture_each(ctx, text, &mut |ctx, res| {
             let inner = EitherCaptures::Left(res.captures.inner);
-            let caps = Captures::new(res.captures.captured_str, inner);
-            (caps, res.rest)
-        } else {
-            let res = self.1.capture(text)?;
-            let inner = EitherCaptures::Right(res.captures.inner);
-            let caps = Captures::new(res.captures.captured_str, inner);
-            (caps, res.rest)
-        };
+            let caps = Captures::new(res.captures.start, res.captures.captured_str, inner);
+            f(ctx, CaptureResult::new(caps, res.rest))
+        });
 
-        Some(CaptureResult::new(caps, rest))
+        left || self.1.capture_each(ctx, text, &mut |ctx, res| {
+            let inner = EitherCaptures::Right(res.captures.inner);
+            let caps = Captures::new(res.captures.start, res.captures.captured_str, inner);
+            f(ctx, CaptureResult::new(caps, res.rest))
+        })
     }
 
-    fn capture_empty(&self, text: &'a str) -> CaptureResult<'a, Self::Inner> {
-        let inner = EitherCaptures::Left(self.0.capture_empty(text).captures.inner);
-        let caps = Captures::new("", inner);
+    fn capture_empty(&self, ctx: &Context<'a, H>, text: &'a H) -> CaptureResult<'a, Self::Inner, H> {
+        let inner = EitherCaptures::Left(self.0.capture_empty(ctx, text).captures.inner);
+        let caps = Captures::new(ctx.position(text), text.split_at(0).0, inner);
         CaptureResult::new(caps, text)
     }
+
+    fn max_width(&self) -> Option<usize> {
+        Some(self.0.max_width()?.max(self.1.max_width()?))
+    }
+
+    fn max_behind(&self) -> Option<usize> {
+        Some(self.0.max_behind()?.max(self.1.max_behind()?))
+    }
 }
 
 #[cfg(test)]
@@ -50,9 +63,9 @@ mod tests {
         let b = Pattern("") | "b";
         assert!(b.test(""));
 
-        // This pattern will match "" first
-        // thus "b" will never match
-        assert!(!b.test("b"));
+        // This pattern will match "" first,
+        // but backtracks to "b" when the rest of text is not empty
+        assert!(b.test("b"));
         assert!(!b.test("a"));
 
         let empty_pattern = Pattern("") | "";
@@ -1,4 +1,7 @@
 
+use std::borrow::Cow;
+use std::io::Read;
+use std::iter::Rev;
 use std::ops::{
     BitOr,
     BitAnd,
@@ -11,44 +14,966 @@ use std::ops::{
     RangeInclusive,
 };
 
-use crate::capture::{Capture, CaptureResult};
-use crate::match_iterator::MatchIterator;
-use crate::patterns::{OrPattern, AndPattern, Cap, ManyPattern, RangePattern};
+use crate::haystack::Haystack;
+use crate::capture::{Capture, CaptureResult, CaptureFn, Context, GroupId};
+use crate::captures::{Captures, Output};
+use crate::read_matches::ReadMatches;
+use crate::push_matcher::PushMatcher;
+use crate::replace::{self, Replacer};
+use crate::split::Split;
+use crate::compile::{Compile, CompileError, Compiled, LazyDfa};
+use crate::char_set::CharSet;
+use crate::match_iterator::{self, MatchIterator};
+use crate::patterns::{
+    OrPattern,
+    LongestPattern,
+    AndPattern,
+    Cap,
+    Backref,
+    Atomic,
+    ManyPattern,
+    RangePattern,
+    LazyPattern,
+    Repeated,
+    Ahead,
+    NotAhead,
+    Behind,
+    NotBehind,
+    Anchor,
+    Map,
+    AndThen,
+    Verify,
+    NoCase,
+};
 
 #[derive(Copy, Clone)]
 pub struct Pattern<S>(pub S);
 
-impl<'a, S: Capture<'a>> Pattern<S> {
-    pub fn matches(self, text: &'a str) -> MatchIterator<'a, S> {
+impl<S> Pattern<S> {
+
+    /// Iterates over non-overlapping matches of the pattern in the text
+    ///
+    /// The search tries positions from left to right, stepping by a char
+    /// (or by a byte when the text is bytes), and takes the preferred match at the first position.
+    /// The next search starts at the end of the match.
+    ///
+    /// Empty matches follow the rule of `str::matches`:
+    /// after an empty match the search continues from the next char,
+    /// so the iteration always advances and an empty match can be found at the end of text.
+    ///
+    /// # Examples
+    /// ```
+    /// # use rep::{Pattern, ScanTerm};
+    /// let x: Vec<usize> = Pattern('x').match_indices("фxыx").map(|(i, _)| i).collect();
+    /// assert_eq!(x, [2, 5]);
+    ///
+    /// let empty: Vec<usize> = Pattern("").match_indices("ab").map(|(i, _)| i).collect();
+    /// assert_eq!(empty, [0, 1, 2]);
+    ///
+    /// let digits = Pattern(|c: char| c.is_ascii_digit()) * ..;
+    /// let numbers: Vec<&str> = digits.matched_strs("a12").collect();
+    /// assert_eq!(numbers, ["", "12", ""]);
+    /// ```
+    ///
+    pub fn matches<'a, H>(self, text: &'a H) -> MatchIterator<'a, S, H> where
+        H: ?Sized + Haystack,
+        S: Capture<'a, H>,
+    {
         MatchIterator::new(self.0, text)
     }
 
-    pub fn matched_strs(self, text: &'a str) -> impl Iterator<Item=&'a str> {
+    /// Iterates over matches starting at every position of the text
+    ///
+    /// Unlike `matches`, the next search starts at the char after the beginning
+    /// of the previous match, so matches can overlap.
+    /// At every position the preferred match is taken.
+    ///
+    /// # Examples
+    /// ```
+    /// # use rep::{Pattern, ScanTerm};
+    /// let aa: Vec<usize> = Pattern("aa").overlapping_matches("aaa").map(|m| m.start).collect();
+    /// assert_eq!(aa, [0, 1]);
+    ///
+    /// let motif = Pattern("AT") & (Pattern('A') | 'T');
+    /// let found: Vec<&str> = motif.overlapping_matches("GATATAT").map(|m| m.captured_str).collect();
+    /// assert_eq!(found, ["ATA", "ATA"]);
+    /// ```
+    ///
+    pub fn overlapping_matches<'a, H>(self, text: &'a H) -> MatchIterator<'a, S, H> where
+        H: ?Sized + Haystack,
+        S: Capture<'a, H>,
+    {
+        MatchIterator::overlapping(self.0, text)
+    }
+
+    pub fn matched_strs<'a, H>(self, text: &'a H) -> impl Iterator<Item=&'a H> where
+        H: ?Sized + Haystack,
+        S: Capture<'a, H>,
+    {
         self.matches(text).map(|m| m.captured_str)
     }
 
-    pub fn test(&self, text: &'a str) -> bool {
-        match self.0.capture(text) {
-            Some(res) => res.rest.is_empty(),
-            None => false,
-        }
+    /// Iterates over matches along with their byte offsets in the text
+    ///
+    /// # Examples
+    /// ```
+    /// # use rep::{Pattern, ScanTerm};
+    /// let number = Pattern(char::is_numeric) * (1..);
+    ///
+    /// let matches: Vec<(usize, &str)> = number.match_indices("a1 23 b456").collect();
+    /// assert_eq!(matches, [(1, "1"), (3, "23"), (7, "456")]);
+    /// ```
+    ///
+    pub fn match_indices<'a, H>(self, text: &'a H) -> impl Iterator<Item=(usize, &'a H)> where
+        H: ?Sized + Haystack,
+        S: Capture<'a, H>,
+    {
+        self.matches(text).map(|m| (m.start, m.captured_str))
+    }
+
+    /// Splits the text by matches of the pattern
+    ///
+    /// Works like `str::split`: the pieces between separators are yielded,
+    /// including empty ones at the edges. Separators are found like `matches` does.
+    ///
+    /// # Examples
+    /// ```
+    /// # use rep::{Pattern, ScanTerm};
+    /// let space = Pattern(char::is_whitespace) * (1..);
+    /// let words: Vec<&str> = space.split("a  b\tc").collect();
+    /// assert_eq!(words, ["a", "b", "c"]);
+    ///
+    /// let comma = Pattern(',') & (Pattern(' ') * ..);
+    /// let items: Vec<&str> = comma.split("a, b,,c,").collect();
+    /// assert_eq!(items, ["a", "b", "", "c", ""]);
+    /// ```
+    ///
+    pub fn split<'a, H>(self, text: &'a H) -> Split<'a, S, H> where
+        H: ?Sized + Haystack,
+        S: Capture<'a, H>,
+    {
+        Split::new(self.0, text)
+    }
+
+    /// Splits the text into at most `limit` pieces
+    ///
+    /// The last piece is the rest of the text after `limit - 1` separators.
+    ///
+    /// # Examples
+    /// ```
+    /// # use rep::{Pattern, ScanTerm};
+    /// let pieces: Vec<&str> = Pattern('=').splitn("key=value=x", 2).collect();
+    /// assert_eq!(pieces, ["key", "value=x"]);
+    /// ```
+    ///
+    pub fn splitn<'a, H>(self, text: &'a H, limit: usize) -> Split<'a, S, H> where
+        H: ?Sized + Haystack,
+        S: Capture<'a, H>,
+    {
+        Split::limited(self.0, text, limit)
+    }
+
+    /// Pieces of `split` in reverse order
+    ///
+    /// Separators are still found from left to right,
+    /// so overlapping separators are chosen like `split` chooses them.
+    /// The whole text is split when the first piece is taken
+    /// and the pieces are kept until they are yielded, see `Split`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use rep::{Pattern, ScanTerm};
+    /// let dot = Pattern('.');
+    /// let labels: Vec<&str> = dot.rsplit("www.example.com").collect();
+    /// assert_eq!(labels, ["com", "example", "www"]);
+    /// ```
+    ///
+    pub fn rsplit<'a, H>(self, text: &'a H) -> Rev<Split<'a, S, H>> where
+        H: ?Sized + Haystack,
+        S: Capture<'a, H>,
+    {
+        self.split(text).rev()
+    }
+
+    /// Splits the text leaving separators at the ends of pieces
+    ///
+    /// Like `str::split_inclusive`, there is no empty piece after the last separator.
+    ///
+    /// # Examples
+    /// ```
+    /// # use rep::{Pattern, ScanTerm};
+    /// let eol = Pattern("\r\n") | '\n';
+    /// let lines: Vec<&str> = eol.split_inclusive("a\nb\r\nc\n").collect();
+    /// assert_eq!(lines, ["a\n", "b\r\n", "c\n"]);
+    /// ```
+    ///
+    pub fn split_inclusive<'a, H>(self, text: &'a H) -> Split<'a, S, H> where
+        H: ?Sized + Haystack,
+        S: Capture<'a, H>,
+    {
+        Split::inclusive(self.0, text)
+    }
+
+    /// Replaces the first match of the pattern
+    ///
+    /// The replacement is a template, a `NoExpand` literal or a closure over captures of the match.
+    /// In a template `$0` is the whole match, `$1`, `$2`, ... are groups in the order of iteration of captures,
+    /// `$name` or `${name}` is the group with the name and `$$` is the `$` char.
+    /// The text is borrowed if nothing is replaced.
+    ///
+    /// # Examples
+    /// ```
+    /// # use rep::{Pattern, ScanTerm, cap};
+    /// let digit = Pattern(|c: char| c.is_ascii_digit());
+    /// let date = cap(digit * 2) & '/' & cap(digit * 2);
+    ///
+    /// assert_eq!(date.replace("10/31 and 12/25", "$2.$1"), "31.10 and 12/25");
+    /// ```
+    ///
+    pub fn replace<'a, R>(self, text: &'a str, rep: R) -> Cow<'a, str> where
+        S: Capture<'a>,
+        R: Replacer<'a, S::Inner>,
+    {
+        self.replacen(text, 1, rep)
+    }
+
+    /// Replaces all matches of the pattern
+    ///
+    /// # Examples
+    /// ```
+    /// # use rep::{Pattern, ScanTerm, Captures, cap_named};
+    /// let word = cap_named("word", Pattern(char::is_alphabetic) * (1..));
+    ///
+    /// assert_eq!(word.clone().replace_all("hello world", "<${word}>"), "<hello> <world>");
+    /// assert_eq!(word.replace_all("hello world", |caps: &Captures<_>| caps.name("word").unwrap().len().to_string()), "5 5");
+    /// ```
+    ///
+    pub fn replace_all<'a, R>(self, text: &'a str, rep: R) -> Cow<'a, str> where
+        S: Capture<'a>,
+        R: Replacer<'a, S::Inner>,
+    {
+        self.replacen(text, 0, rep)
+    }
+
+    /// Replaces first `limit` matches of the pattern, all of them if the limit is zero
+    ///
+    /// # Examples
+    /// ```
+    /// # use rep::{Pattern, ScanTerm, NoExpand};
+    /// let space = Pattern(' ');
+    /// assert_eq!(space.replacen("a b c d", 2, NoExpand("$")), "a$b$c d");
+    /// ```
+    ///
+    pub fn replacen<'a, R>(self, text: &'a str, limit: usize, rep: R) -> Cow<'a, str> where
+        S: Capture<'a>,
+        R: Replacer<'a, S::Inner>,
+    {
+        replace::replacen(text, self.matches(text), limit, rep)
+    }
+
+    /// Iterates over matches in the input of a reader
+    ///
+    /// The input is read in chunks, matches crossing the boundaries of chunks are found too.
+    /// Matches own their text and know their position in the stream.
+    /// A text stream which is not valid UTF-8 results in `InvalidData` error.
+    ///
+    /// # Examples
+    /// ```
+    /// # use rep::{Pattern, ScanTerm, ReadMatches};
+    /// let number = Pattern(|c: char| c.is_ascii_digit()) * (1..);
+    /// let log = "a=1 b=22 c=333".as_bytes();
+    ///
+    /// let numbers: Vec<String> = ReadMatches::with_capacity(number, log, 4)
+    ///     .map(|m| m.unwrap().text)
+    ///     .collect();
+    /// assert_eq!(numbers, ["1", "22", "333"]);
+    ///
+    /// let error = Pattern("error").read_matches(&b"no error"[..]).next().unwrap().unwrap();
+    /// assert_eq!(error.span().range(), 3..8);
+    /// ```
+    ///
+    pub fn read_matches<R, H>(self, reader: R) -> ReadMatches<S, R, H> where
+        R: Read,
+        H: ?Sized + Haystack,
+        S: for<'a> Capture<'a, H>,
+    {
+        ReadMatches::new(self.0, reader)
+    }
+
+    /// Matcher of input which arrives in pieces
+    ///
+    /// The pattern is matched again over the whole input on every piece, see `PushMatcher` for details.
+    /// A compilable pattern is better matched by the push matcher of the compiled program,
+    /// which carries the state of the matching between pieces.
+    ///
+    /// # Examples
+    /// ```
+    /// # use rep::{Pattern, ScanTerm, Status};
+    /// let line = (Pattern(|c: char| c != '\n') * ..) & '\n';
+    /// let compiled = line.compile().unwrap();
+    /// let mut matcher = compiled.push_matcher();
+    ///
+    /// for _ in 0..10_000 {
+    ///     assert_eq!(matcher.feed("x"), Status::Incomplete);
+    /// }
+    /// assert_eq!(matcher.feed("\n"), Status::Matched);
+    ///
+    /// let mut matcher = Pattern("ok\n").push_matcher();
+    /// assert_eq!(matcher.feed("ok\nnext"), Status::Matched);
+    /// ```
+    ///
+    pub fn push_matcher<H>(self) -> PushMatcher<S, H> where
+        H: ?Sized + Haystack,
+        S: for<'a> Capture<'a, H>,
+    {
+        PushMatcher::new(self.0)
+    }
+
+    /// Checks the whole text matches the pattern
+    ///
+    /// # Examples
+    /// ```
+    /// # use rep::{Pattern, ScanTerm};
+    /// let header = Pattern(b"\x89PNG") & (Pattern(|b: u8| b != 0) * ..);
+    /// assert!(header.test(&b"\x89PNG\r\n"[..]));
+    /// assert!(!header.test(&b"\x89PNG\0"[..]));
+    /// ```
+    ///
+    pub fn test<'a, H>(&self, text: &'a H) -> bool where
+        H: ?Sized + Haystack,
+        S: Capture<'a, H>,
+    {
+        let mut ctx = Context::new(text);
+        self.0.capture_each(&mut ctx, text, &mut |_, res| res.rest.is_empty())
+    }
+
+    /// Checks the whole text matches the pattern, the same as `test`
+    pub fn is_full_match<'a, H>(&self, text: &'a H) -> bool where
+        H: ?Sized + Haystack,
+        S: Capture<'a, H>,
+    {
+        self.test(text)
+    }
+
+    /// Checks the pattern matches anywhere in the text
+    ///
+    /// # Examples
+    /// ```
+    /// # use rep::{Pattern, ScanTerm};
+    /// let error = Pattern("ERROR") | "FATAL";
+    /// assert!(error.is_match("12:00 FATAL disk is full"));
+    /// assert!(!error.is_match("12:00 INFO ok"));
+    /// ```
+    ///
+    pub fn is_match<'a, H>(&self, text: &'a H) -> bool where
+        H: ?Sized + Haystack,
+        S: Capture<'a, H>,
+    {
+        self.find(text).is_some()
+    }
+
+    /// Matches the pattern at the beginning of the text
+    ///
+    /// Returns the preferred match along with the rest of the text after it.
+    ///
+    /// # Examples
+    /// ```
+    /// # use rep::{Pattern, ScanTerm};
+    /// let number = Pattern(|c: char| c.is_ascii_digit()) * (1..);
+    ///
+    /// let (caps, rest) = number.match_prefix("123abc").unwrap();
+    /// assert_eq!(caps.captured_len(), 3);
+    /// assert_eq!(rest, "abc");
+    ///
+    /// assert!(number.match_prefix("abc123").is_none());
+    /// ```
+    ///
+    pub fn match_prefix<'a, H>(&self, text: &'a H) -> Option<(Captures<'a, S::Inner, H>, &'a H)> where
+        H: ?Sized + Haystack,
+        S: Capture<'a, H>,
+    {
+        let mut ctx = Context::new(text);
+        self.0.capture(&mut ctx, text).map(Into::into)
+    }
+
+    /// Finds the first match of the pattern in the text
+    ///
+    /// # Examples
+    /// ```
+    /// # use rep::{Pattern, ScanTerm, cap};
+    /// let key = cap(Pattern(char::is_alphabetic) * (1..)) & '=';
+    ///
+    /// let caps = key.find("1 + size=3").unwrap();
+    /// assert_eq!(caps.span().range(), 4..9);
+    /// assert_eq!(caps.collect::<Vec<_>>(), ["size=", "size"]);
+    /// ```
+    ///
+    pub fn find<'a, H>(&self, text: &'a H) -> Option<Captures<'a, S::Inner, H>> where
+        H: ?Sized + Haystack,
+        S: Capture<'a, H>,
+    {
+        self.find_at(text, 0)
+    }
+
+    /// Finds the first match of the pattern starting at the position (in bytes) or after it
+    ///
+    /// The text before the position is seen by anchors and lookbehinds,
+    /// and spans of the match are offsets in the whole text.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the position is not at a char boundary or is out of the text.
+    ///
+    /// # Examples
+    /// ```
+    /// # use rep::{Pattern, ScanTerm, word_boundary};
+    /// let word = word_boundary() & "cat" & word_boundary();
+    ///
+    /// assert_eq!(word.find_at("cat concat cat", 1).map(|m| m.start), Some(11));
+    /// ```
+    ///
+    pub fn find_at<'a, H>(&self, text: &'a H, start: usize) -> Option<Captures<'a, S::Inner, H>> where
+        H: ?Sized + Haystack,
+        S: Capture<'a, H>,
+    {
+        let mut ctx = Context::new(text);
+        match_iterator::find(&self.0, &mut ctx, text.split_at(start).1).map(|res| res.captures)
+    }
+
+    /// Compiles the pattern into an NFA which searches in linear time
+    ///
+    /// Only patterns built of literals, single-char terms, sequences, alternations,
+    /// repetitions, groups, `nocase` and anchors can be compiled.
+    /// Fails if the program exceeds 2^20 instructions, see `Compiled::with_size_limit`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use rep::{Pattern, ScanTerm};
+    /// let nested = ((Pattern('a') * ..) * ..) & 'b';
+    /// let compiled = nested.compile().unwrap();
+    ///
+    /// assert!(!compiled.is_match(&"a".repeat(1000)));
+    /// assert_eq!(compiled.find("xaab").unwrap().span().range(), 1..4);
+    /// ```
+    ///
+    pub fn compile(&self) -> Result<Compiled<'_>, CompileError> where S: Compile {
+        Compiled::new(&self.0)
+    }
+
+    /// Compiles the pattern into a lazily built DFA which checks and counts matches
+    ///
+    /// Fails like `compile` if the program is too big.
+    ///
+    /// # Examples
+    /// ```
+    /// # use rep::{Pattern, ScanTerm};
+    /// let digit = Pattern(|c: char| c.is_ascii_digit());
+    /// let number = digit * (1..);
+    /// let mut dfa = number.lazy_dfa().unwrap();
+    ///
+    /// assert!(dfa.test("2024"));
+    /// assert!(dfa.is_match("year 2024"));
+    /// assert_eq!(dfa.count("1, 22, 333"), 3);
+    /// ```
+    ///
+    pub fn lazy_dfa(&self) -> Result<LazyDfa<'_>, CompileError> where S: Compile {
+        self.compile().map(LazyDfa::new)
+    }
+
+    /// Matches the whole text and returns the value produced by the pattern
+    ///
+    /// # Examples
+    /// ```
+    /// # use rep::{Pattern, ScanTerm};
+    /// let digit = Pattern(|c: char| c.is_ascii_digit());
+    /// let number = (digit * (1..)).map(|s| s.parse::<u32>().unwrap());
+    ///
+    /// assert_eq!(number.parse("42"), Some(42));
+    /// assert_eq!(number.parse("42!"), None);
+    /// ```
+    ///
+    pub fn parse<'a, H>(&self, text: &'a H) -> Option<<S::Inner as Output<'a, H>>::Value> where
+        H: ?Sized + Haystack,
+        S: Capture<'a, H>,
+        S::Inner: Output<'a, H>,
+    {
+        let mut ctx = Context::new(text);
+        let mut value = None;
+        self.0.capture_each(&mut ctx, text, &mut |_, res| {
+            if res.rest.is_empty() {
+                value = res.captures.value();
+            }
+
+            res.rest.is_empty()
+        });
+
+        value
+    }
+
+    /// Transforms the value produced by the pattern
+    ///
+    /// The function receives the matched text,
+    /// or the value of the previous transformation.
+    ///
+    /// # Examples
+    /// ```
+    /// # use rep::{Pattern, ScanTerm};
+    /// let word = (Pattern(char::is_alphabetic) * (1..)).map(|s| s.to_uppercase());
+    /// assert_eq!(word.parse("abc"), Some("ABC".to_string()));
+    /// ```
+    ///
+    pub fn map<'a, H, F, T>(self, f: F) -> Pattern<Map<S, F>> where
+        H: ?Sized + Haystack,
+        S: Capture<'a, H>,
+        S::Inner: Output<'a, H>,
+        F: Fn(<S::Inner as Output<'a, H>>::Value) -> T,
+    {
+        Pattern(Map(self.0, f))
+    }
+
+    /// Transforms the value produced by the pattern with a fallible function
+    ///
+    /// If the function returns `None` the match is rejected
+    /// and the expression tries the next one.
+    ///
+    /// # Examples
+    /// ```
+    /// # use rep::{Pattern, ScanTerm};
+    /// let digit = Pattern(|c: char| c.is_ascii_digit());
+    /// let byte = (digit * (1..)).and_then(|s| s.parse::<u8>().ok());
+    ///
+    /// assert_eq!(byte.parse("255"), Some(255));
+    /// assert_eq!(byte.parse("256"), None);
+    /// ```
+    ///
+    pub fn and_then<'a, H, F, T>(self, f: F) -> Pattern<AndThen<S, F>> where
+        H: ?Sized + Haystack,
+        S: Capture<'a, H>,
+        S::Inner: Output<'a, H>,
+        F: Fn(<S::Inner as Output<'a, H>>::Value) -> Option<T>,
+    {
+        Pattern(AndThen(self.0, f))
+    }
+
+    /// Accepts only matches which produce a value satisfying the predicate
+    ///
+    /// # Examples
+    /// ```
+    /// # use rep::{Pattern, ScanTerm};
+    /// let digit = Pattern(|c: char| c.is_ascii_digit());
+    /// let number = (digit * (1..)).map(|s| s.parse::<u32>().unwrap());
+    /// let port = number.verify(|n| *n <= 65535);
+    ///
+    /// assert!(port.test("8080"));
+    /// assert!(!port.test("80800"));
+    /// ```
+    ///
+    pub fn verify<'a, H, F>(self, f: F) -> Pattern<Verify<S, F>> where
+        H: ?Sized + Haystack,
+        S: Capture<'a, H>,
+        S::Inner: Output<'a, H>,
+        F: Fn(&<S::Inner as Output<'a, H>>::Value) -> bool,
+    {
+        Pattern(Verify(self.0, f))
+    }
+}
+
+impl<P> Pattern<P> {
+
+    /// Keeps captures of every iteration of the repetition
+    ///
+    /// By default a repetition reports groups of its last iteration only.
+    /// The repeated pattern reports groups of all iterations
+    /// and gives access to each of them with `Captures::repeated`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use rep::{Pattern, ScanTerm, cap};
+    /// let word = cap(Pattern(char::is_alphabetic) * (1..));
+    /// let words = ((word & ' ') * ..).repeated();
+    ///
+    /// let caps: Vec<&str> = words.matches("ab cd ").next().unwrap().collect();
+    /// assert_eq!(caps, ["ab cd ", "ab", "cd"]);
+    /// ```
+    ///
+    pub fn repeated(self) -> Pattern<Repeated<P>> {
+        Pattern(Repeated(self.0))
     }
 }
 
-impl<'a, S: Capture<'a>> Capture<'a> for Pattern<S> {
+impl<'a, H: ?Sized + Haystack, S: Capture<'a, H>> Capture<'a, H> for Pattern<S> {
     type Inner = S::Inner;
 
-    fn capture(&self, text: &'a str) -> Option<CaptureResult<'a, Self::Inner>> {
-        self.0.capture(text)
+    fn capture_each(
+        &self,
+        ctx: &mut Context<'a, H>,
+        text: &'a H,
+        f: &mut CaptureFn<'_, 'a, Self::Inner, H>,
+    ) -> bool {
+        self.0.capture_each(ctx, text, f)
+    }
+
+    fn capture_empty(&self, ctx: &Context<'a, H>, text: &'a H) -> CaptureResult<'a, Self::Inner, H> {
+        self.0.capture_empty(ctx, text)
+    }
+
+    fn is_simple(&self) -> bool {
+        self.0.is_simple()
+    }
+
+    fn max_width(&self) -> Option<usize> {
+        self.0.max_width()
+    }
+
+    fn max_behind(&self) -> Option<usize> {
+        self.0.max_behind()
+    }
+}
+
+pub fn cap<P>(pattern: P) -> Pattern<Cap<P>> {
+    Pattern(Cap(pattern, GroupId::new(), None))
+}
+
+/// Named capture group
+///
+/// The text captured by the group can be found by the name
+/// with `Captures::name`, so it doesn't depend on the position of the group.
+///
+/// # Examples
+/// ```
+/// # use rep::{Pattern, ScanTerm, cap_named};
+/// let key = cap_named("key", Pattern(char::is_alphabetic) * (1..));
+/// let value = cap_named("value", Pattern(char::is_numeric) * (1..));
+/// let pair = key & '=' & value;
+///
+/// let caps = pair.matches("size=12").next().unwrap();
+/// assert_eq!(caps.name("key"), Some("size"));
+/// assert_eq!(caps.name("value"), Some("12"));
+/// ```
+///
+pub fn cap_named<P>(name: &'static str, pattern: P) -> Pattern<Cap<P>> {
+    Pattern(Cap(pattern, GroupId::new(), Some(name)))
+}
+
+/// Backreference to a capture group
+///
+/// Matches the same text as the group matched on the left of the backreference.
+/// The group must be created by `cap` before.
+///
+/// # Examples
+/// ```
+/// # use rep::{Pattern, ScanTerm, cap, backref};
+/// let quote = cap(Pattern('\'') | '"');
+/// let body = Pattern(|c: char| c != '\'' && c != '"') * ..;
+/// let string = quote & body & backref(&quote);
+///
+/// assert!(string.test("'single'"));
+/// assert!(string.test("\"double\""));
+/// assert!(!string.test("'mixed\""));
+/// ```
+///
+pub fn backref<S>(group: &Pattern<Cap<S>>) -> Pattern<Backref> {
+    Pattern(Backref((group.0).1))
+}
+
+/// Atomic group of pattern
+///
+/// The group never gives back the text it matched,
+/// so the pattern is not backtracked when the rest of expression fails.
+/// It keeps matching linear on hot paths.
+///
+/// # Examples
+/// ```
+/// # use rep::{Pattern, ScanTerm, atomic};
+/// let possessive = atomic(Pattern('a') * ..) & 'a';
+/// assert!(!possessive.test("aa"));
+///
+/// let greedy = (Pattern('a') * ..) & 'a';
+/// assert!(greedy.test("aa"));
+/// ```
+///
+pub fn atomic<P>(pattern: P) -> Pattern<Atomic<P>> {
+    Pattern(Atomic(pattern))
+}
+
+/// Leftmost-longest (POSIX) alternation of patterns
+///
+/// Tries both patterns and takes the longest match.
+/// If both matches have the same length the first pattern wins.
+///
+/// # Examples
+/// ```
+/// # use rep::{Pattern, ScanTerm, longest};
+/// let keyword = Pattern("if");
+/// let identifier = Pattern(char::is_alphabetic) * (1..);
+/// let token = longest(keyword, identifier);
+///
+/// let tokens: Vec<&str> = token.matched_strs("if iffy").collect();
+/// assert_eq!(tokens, ["if", "iffy"]);
+/// ```
+///
+pub fn longest<A, B>(a: A, b: B) -> Pattern<LongestPattern<A, B>> {
+    Pattern(LongestPattern(a, b))
+}
+
+/// Positive lookahead
+///
+/// Zero-width assertion: checks the pattern matches after the current position,
+/// but consumes nothing and captures nothing.
+///
+/// # Examples
+/// ```
+/// # use rep::{Pattern, ScanTerm, ahead};
+/// let call = Pattern(char::is_alphabetic) * (1..) & ahead('(');
+/// let calls: Vec<&str> = call.matched_strs("foo(x) bar").collect();
+/// assert_eq!(calls, ["foo"]);
+/// ```
+///
+pub fn ahead<P>(pattern: P) -> Pattern<Ahead<P>> {
+    Pattern(Ahead(pattern))
+}
+
+/// Negative lookahead
+///
+/// Zero-width assertion: checks the pattern doesn't match after the current position.
+///
+/// # Examples
+/// ```
+/// # use rep::{Pattern, ScanTerm, not_ahead};
+/// let name = Pattern(char::is_alphabetic) * (1..) & not_ahead(Pattern('(') | char::is_alphabetic);
+/// let names: Vec<&str> = name.matched_strs("foo(x) bar").collect();
+/// assert_eq!(names, ["x", "bar"]);
+/// ```
+///
+pub fn not_ahead<P>(pattern: P) -> Pattern<NotAhead<P>> {
+    Pattern(NotAhead(pattern))
+}
+
+/// Positive lookbehind
+///
+/// Zero-width assertion: checks some text which ends at the current position matches the pattern.
+/// The pattern is tried from the positions within its maximum width,
+/// a pattern of unbounded width is tried from every earlier position, which takes quadratic time.
+///
+/// # Examples
+/// ```
+/// # use rep::{Pattern, ScanTerm, behind};
+/// let cents = behind('.') & Pattern(char::is_numeric) * (1..);
+/// let matches: Vec<&str> = cents.matched_strs("12.50").collect();
+/// assert_eq!(matches, ["50"]);
+/// ```
+///
+pub fn behind<P>(pattern: P) -> Pattern<Behind<P>> {
+    Pattern(Behind(pattern))
+}
+
+/// Negative lookbehind
+///
+/// Zero-width assertion: checks no text which ends at the current position matches the pattern.
+///
+/// # Examples
+/// ```
+/// # use rep::{Pattern, ScanTerm, not_behind};
+/// let digit = Pattern(char::is_numeric);
+/// let integer = not_behind(Pattern('.') | char::is_numeric) & digit * (1..);
+/// let matches: Vec<&str> = integer.matched_strs("12.50").collect();
+/// assert_eq!(matches, ["12"]);
+/// ```
+///
+pub fn not_behind<P>(pattern: P) -> Pattern<NotBehind<P>> {
+    Pattern(NotBehind(pattern))
+}
+
+/// Anchor at the beginning of the input
+///
+/// # Examples
+/// ```
+/// # use rep::{Pattern, ScanTerm, start};
+/// let first = start() & "xy";
+/// let matches: Vec<&str> = first.matched_strs("xy xy").collect();
+/// assert_eq!(matches, ["xy"]);
+/// ```
+///
+pub fn start() -> Pattern<Anchor> {
+    Pattern(Anchor::Start)
+}
+
+/// Anchor at the end of the input
+///
+/// # Examples
+/// ```
+/// # use rep::{Pattern, ScanTerm, end};
+/// let last = Pattern("xy") & end();
+/// let matches: Vec<&str> = last.matched_strs("xy xy").collect();
+/// assert_eq!(matches, ["xy"]);
+/// ```
+///
+pub fn end() -> Pattern<Anchor> {
+    Pattern(Anchor::End)
+}
+
+/// Anchor at the beginning of a line
+///
+/// Matches at the beginning of the input or after a line terminator
+/// (`\n`, `\r\n` or `\r`).
+///
+/// # Examples
+/// ```
+/// # use rep::{Pattern, ScanTerm, line_start};
+/// let first = line_start() & Pattern(char::is_alphabetic) * (1..);
+/// let matches: Vec<&str> = first.matched_strs("ab cd\r\nef").collect();
+/// assert_eq!(matches, ["ab", "ef"]);
+/// ```
+///
+pub fn line_start() -> Pattern<Anchor> {
+    Pattern(Anchor::LineStart)
+}
+
+/// Anchor at the end of a line
+///
+/// Matches at the end of the input or before a line terminator
+/// (`\n`, `\r\n` or `\r`).
+///
+/// # Examples
+/// ```
+/// # use rep::{Pattern, ScanTerm, line_end};
+/// let last = Pattern(char::is_alphabetic) * (1..) & line_end();
+/// let matches: Vec<&str> = last.matched_strs("ab cd\r\nef").collect();
+/// assert_eq!(matches, ["cd", "ef"]);
+/// ```
+///
+pub fn line_end() -> Pattern<Anchor> {
+    Pattern(Anchor::LineEnd)
+}
+
+/// Anchor at a word boundary
+///
+/// Word chars are alphanumeric chars and `_`.
+/// The edges of the input count as non-word chars.
+///
+/// # Examples
+/// ```
+/// # use rep::{Pattern, ScanTerm, word_boundary};
+/// let xy = word_boundary() & "xy" & word_boundary();
+/// let matches: Vec<&str> = xy.matched_strs("xxy xy").collect();
+/// assert_eq!(matches, ["xy"]);
+/// ```
+///
+pub fn word_boundary() -> Pattern<Anchor> {
+    Pattern(Anchor::WordBoundary)
+}
+
+/// Anchor at a position which is not a word boundary
+///
+/// # Examples
+/// ```
+/// # use rep::{Pattern, ScanTerm, not_word_boundary};
+/// let inner = not_word_boundary() & 'x' & not_word_boundary();
+/// let matches: Vec<&str> = inner.matched_strs("x axb").collect();
+/// assert_eq!(matches, ["x"]);
+/// ```
+///
+pub fn not_word_boundary() -> Pattern<Anchor> {
+    Pattern(Anchor::NotWordBoundary)
+}
+
+/// Case-insensitive pattern
+///
+/// Strings, chars and char ranges of the pattern are matched
+/// with simple Unicode case folding. Predicates are matched as is.
+///
+/// # Examples
+/// ```
+/// # use rep::{Pattern, ScanTerm, nocase};
+/// let keyword = nocase(Pattern("select") | "from");
+/// let keywords: Vec<&str> = keyword.matched_strs("SELECT a FROM b").collect();
+/// assert_eq!(keywords, ["SELECT", "FROM"]);
+/// ```
+///
+pub fn nocase<P>(pattern: P) -> Pattern<NoCase<P>> {
+    Pattern(NoCase(pattern))
+}
+
+/// Pattern which matches a single char which is one of the array
+///
+/// The array is built into a `CharSet`, so the lookup doesn't depend on the number of chars.
+///
+impl<const N: usize> From<[char; N]> for Pattern<CharSet> {
+    fn from(chars: [char; N]) -> Pattern<CharSet> {
+        Pattern(CharSet::from(chars))
     }
+}
 
-    fn capture_empty(&self, text: &'a str) -> CaptureResult<'a, Self::Inner> {
-        self.0.capture_empty(text)
+/// Pattern which matches a single char which is one of the slice
+///
+/// The slice is built into a `CharSet`, it doesn't need to be sorted.
+///
+impl From<&[char]> for Pattern<CharSet> {
+    fn from(chars: &[char]) -> Pattern<CharSet> {
+        Pattern(CharSet::from(chars))
     }
 }
 
-pub fn cap<'a, P: Capture<'a>>(pattern: P) -> Pattern<Cap<P>> {
-    Pattern(Cap(pattern))
+/// Matches a single char which is one of the chars of the string
+///
+/// # Examples
+/// ```
+/// # use rep::{Pattern, ScanTerm, one_of};
+/// let vowels = one_of("aeiou") * (1..);
+/// let matches: Vec<&str> = vowels.matched_strs("queue").collect();
+/// assert_eq!(matches, ["ueue"]);
+/// ```
+///
+pub fn one_of(chars: &str) -> Pattern<CharSet> {
+    Pattern(CharSet::new(chars.chars()))
+}
+
+/// Matches a single char which is none of the chars of the string
+///
+/// # Examples
+/// ```
+/// # use rep::{Pattern, ScanTerm, none_of};
+/// let field = none_of(",;") * (1..);
+/// let fields: Vec<&str> = field.matched_strs("a,bc;d").collect();
+/// assert_eq!(fields, ["a", "bc", "d"]);
+/// ```
+///
+pub fn none_of(chars: &str) -> Pattern<CharSet> {
+    Pattern(CharSet::negated(chars.chars()))
+}
+
+impl<S> Pattern<ManyPattern<S>> {
+
+    /// Makes the repetition lazy
+    ///
+    /// The number of repetitions is fixed,
+    /// so the lazy pattern matches the same text as the greedy one.
+    ///
+    pub fn lazy(self) -> Pattern<LazyPattern<S, RangeInclusive<u32>>> {
+        let ManyPattern(pattern, count) = self.0;
+        Pattern(LazyPattern(pattern, count..=count))
+    }
+}
+
+impl<S, R> Pattern<RangePattern<S, R>> {
+
+    /// Makes the repetition lazy (non-greedy)
+    ///
+    /// The lazy pattern tries the minimum number of repetitions first
+    /// and grows only when the rest of expression fails to match.
+    ///
+    /// # Examples
+    /// ```
+    /// # use rep::{Pattern, ScanTerm};
+    /// let lazy = (Pattern('a') * (1..)).lazy();
+    /// let matches: Vec<&str> = lazy.matched_strs("aaa").collect();
+    /// assert_eq!(matches, ["a", "a", "a"]);
+    /// ```
+    ///
+    pub fn lazy(self) -> Pattern<LazyPattern<S, R>> {
+        let RangePattern(pattern, range) = self.0;
+        Pattern(LazyPattern(pattern, range))
+    }
 }
 
 /// Combination of patterns with `|` operator (alteration)
@@ -61,7 +986,7 @@ pub fn cap<'a, P: Capture<'a>>(pattern: P) -> Pattern<Cap<P>> {
 /// assert!(a_or_b.test("b"));
 /// ```
 ///
-impl<'a, A: Capture<'a>, B: Capture<'a>> BitOr<B> for Pattern<A> {
+impl<A, B> BitOr<B> for Pattern<A> {
     type Output = Pattern<OrPattern<A, B>>;
 
     fn bitor(self, rhs: B) -> Self::Output {
@@ -78,7 +1003,7 @@ impl<'a, A: Capture<'a>, B: Capture<'a>> BitOr<B> for Pattern<A> {
 /// assert!(ab.test("ab"));
 /// ```
 ///
-impl<'a, A: Capture<'a>, B: Capture<'a>> BitAnd<B> for Pattern<A> {
+impl<A, B> BitAnd<B> for Pattern<A> {
     type Output = Pattern<AndPattern<A, B>>;
 
     fn bitand(self, rhs: B) -> Self::Output {
@@ -97,7 +1022,7 @@ impl<'a, A: Capture<'a>, B: Capture<'a>> BitAnd<B> for Pattern<A> {
 /// assert!(aaa.test("aaa"));
 /// ```
 ///
-impl<'a, A: Capture<'a>> Mul<u32> for Pattern<A> {
+impl<A> Mul<u32> for Pattern<A> {
     type Output = Pattern<ManyPattern<A>>;
 
     fn mul(self, rhs: u32) -> Self::Output {
@@ -118,7 +1043,7 @@ impl<'a, A: Capture<'a>> Mul<u32> for Pattern<A> {
 /// assert!(full.test("aa"));
 /// ```
 ///
-impl<'a, A: Capture<'a>> Mul<RangeFull> for Pattern<A> {
+impl<A> Mul<RangeFull> for Pattern<A> {
     type Output = Pattern<RangePattern<A, RangeFull>>;
 
     fn mul(self, rhs: RangeFull) -> Self::Output {
@@ -138,7 +1063,7 @@ impl<'a, A: Capture<'a>> Mul<RangeFull> for Pattern<A> {
 /// assert!(from.test("aaa"));
 /// ```
 ///
-impl<'a, A: Capture<'a>> Mul<RangeFrom<u32>> for Pattern<A> {
+impl<A> Mul<RangeFrom<u32>> for Pattern<A> {
     type Output = Pattern<RangePattern<A, RangeFrom<u32>>>;
 
     fn mul(self, rhs: RangeFrom<u32>) -> Self::Output {
@@ -159,7 +1084,7 @@ impl<'a, A: Capture<'a>> Mul<RangeFrom<u32>> for Pattern<A> {
 /// assert!(to.test("aa"));
 /// ```
 ///
-impl<'a, A: Capture<'a>> Mul<RangeTo<u32>> for Pattern<A> {
+impl<A> Mul<RangeTo<u32>> for Pattern<A> {
     type Output = Pattern<RangePattern<A, RangeTo<u32>>>;
 
     fn mul(self, rhs: RangeTo<u32>) -> Self::Output {
@@ -180,7 +1105,7 @@ impl<'a, A: Capture<'a>> Mul<RangeTo<u32>> for Pattern<A> {
 /// assert!(to_inclusive.test("aa"));
 /// ```
 ///
-impl<'a, A: Capture<'a>> Mul<RangeToInclusive<u32>> for Pattern<A> {
+impl<A> Mul<RangeToInclusive<u32>> for Pattern<A> {
     type Output = Pattern<RangePattern<A, RangeToInclusive<u32>>>;
 
     fn mul(self, rhs: RangeToInclusive<u32>) -> Self::Output {
@@ -200,7 +1125,7 @@ impl<'a, A: Capture<'a>> Mul<RangeToInclusive<u32>> for Pattern<A> {
 /// assert!(range.test("a"));
 /// ```
 ///
-impl<'a, A: Capture<'a>> Mul<Range<u32>> for Pattern<A> {
+impl<A> Mul<Range<u32>> for Pattern<A> {
     type Output = Pattern<RangePattern<A, Range<u32>>>;
 
     fn mul(self, rhs: Range<u32>) -> Self::Output {
@@ -221,7 +1146,7 @@ impl<'a, A: Capture<'a>> Mul<Range<u32>> for Pattern<A> {
 /// assert!(range_inclusive.test("aa"));
 /// ```
 ///
-impl<'a, A: Capture<'a>> Mul<RangeInclusive<u32>> for Pattern<A> {
+impl<A> Mul<RangeInclusive<u32>> for Pattern<A> {
     type Output = Pattern<RangePattern<A, RangeInclusive<u32>>>;
 
     fn mul(self, rhs: RangeInclusive<u32>) -> Self::Output {
@@ -232,6 +1157,7 @@ impl<'a, A: Capture<'a>> Mul<RangeInclusive<u32>> for Pattern<A> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::captures::Span;
 
     #[test]
     fn pattern_str() {
@@ -265,6 +1191,86 @@ mod tests {
         assert_eq!(x_y_matches, ["x", "y", "y", "x"]);
     }
 
+    #[test]
+    fn non_ascii_matches() {
+        let x_matches: Vec<&str> = Pattern('x').matched_strs("фx").collect();
+        assert_eq!(x_matches, ["x"]);
+
+        let text = "日本 x ЖЖx ß";
+        let expected: Vec<(usize, &str)> = text.match_indices('x').collect();
+        let indices: Vec<(usize, &str)> = Pattern('x').match_indices(text).collect();
+        assert_eq!(indices, expected);
+
+        let cyrillic = Pattern('А'..='я') * (1..);
+        let words: Vec<&str> = cyrillic.matched_strs("тест x ЖЖx").collect();
+        assert_eq!(words, ["тест", "ЖЖ"]);
+    }
+
+    #[test]
+    fn empty_matches() {
+        for text in ["", "abc", "фы", "a-b"] {
+            let expected: Vec<(usize, &str)> = text.match_indices("").collect();
+            let indices: Vec<(usize, &str)> = Pattern("").match_indices(text).collect();
+            assert_eq!(indices, expected);
+        }
+
+        let digits = Pattern(|c: char| c.is_ascii_digit()) * ..;
+        let indices: Vec<(usize, &str)> = digits.match_indices("ф12ы3").collect();
+        assert_eq!(indices, [(0, ""), (2, "12"), (4, ""), (6, "3"), (7, "")]);
+
+        let bytes: Vec<(usize, &[u8])> = Pattern(&b""[..]).match_indices(&[0xFF, 0xFE][..]).collect();
+        assert_eq!(bytes.iter().map(|(i, _)| *i).collect::<Vec<_>>(), [0, 1, 2]);
+    }
+
+    #[test]
+    fn overlapping_matches() {
+        let aa: Vec<(usize, &str)> = Pattern("aa").overlapping_matches("aaaa").map(|m| (m.start, m.captured_str)).collect();
+        assert_eq!(aa, [(0, "aa"), (1, "aa"), (2, "aa")]);
+
+        let greedy = Pattern('ж') * (1..);
+        let runs: Vec<&str> = greedy.overlapping_matches("жжxж").map(|m| m.captured_str).collect();
+        assert_eq!(runs, ["жж", "ж", "ж"]);
+
+        let empty: Vec<usize> = Pattern("").overlapping_matches("ab").map(|m| m.start).collect();
+        assert_eq!(empty, [0, 1, 2]);
+
+        let pair = cap(Pattern(char::is_alphabetic)) & cap(Pattern(char::is_alphabetic));
+        let pairs: Vec<Vec<&str>> = pair.overlapping_matches("abc").map(|caps| caps.collect()).collect();
+        assert_eq!(pairs, [["ab", "a", "b"], ["bc", "b", "c"]]);
+
+        let bytes: Vec<usize> = Pattern(b"\x00\x00").overlapping_matches(&[0, 0, 0][..]).map(|m| m.start).collect();
+        assert_eq!(bytes, [0, 1]);
+    }
+
+    #[test]
+    fn entry_points() {
+        let number = Pattern(|c: char| c.is_ascii_digit()) * (1..);
+
+        assert!(number.is_full_match("123"));
+        assert!(!number.is_full_match("123a"));
+        assert!(number.is_match("a123"));
+        assert!(!number.is_match("abc"));
+        assert!(!number.is_match(""));
+
+        let (caps, rest) = number.match_prefix("12 34").unwrap();
+        assert_eq!((caps.captured_str, rest), ("12", " 34"));
+        assert!(number.match_prefix(" 12").is_none());
+
+        let found = number.find("ab 12 34").unwrap();
+        assert_eq!(found.span(), Span::new(3, 5));
+        assert_eq!(number.find_at("ab 12 34", 4).unwrap().captured_str, "2");
+        assert_eq!(number.find_at("ab 12 34", 5).unwrap().span(), Span::new(6, 8));
+        assert!(number.find_at("ab 12 34", 8).is_none());
+
+        let after_x = behind('x') & 'y';
+        assert_eq!(after_x.find_at("xy", 1).map(|m| m.start), Some(1));
+        assert!(after_x.match_prefix("y").is_none());
+
+        let field = Pattern(|b: u8| b != 0) * (1..);
+        assert_eq!(field.find_at(&b"a\0bc"[..], 1).unwrap().captured_str, b"bc");
+        assert!(field.is_match(&b"\0\xFF"[..]));
+    }
+
     #[test]
     fn pattern_caps() {
         let x = Pattern('x');
@@ -279,6 +1285,34 @@ mod tests {
         assert!(xy_matches.next().is_none());
     }
 
+    #[test]
+    fn pattern_spans() {
+        let digit = Pattern(|c: char| c.is_ascii_digit());
+        let time = cap(digit * (1..=2)) & ':' & cap((digit * 2) | "xx");
+        let mut matches = time.matches("at 9:30, 12:xx");
+
+        let caps = matches.next().unwrap();
+        assert_eq!(caps.span(), Span::new(3, 7));
+        assert_eq!(caps.spans(), [Span::new(3, 7), Span::new(3, 4), Span::new(5, 7)]);
+
+        let caps = matches.next().unwrap();
+        assert_eq!(caps.span().range(), 9..14);
+        assert_eq!(caps.spans(), [Span::new(9, 14), Span::new(9, 11), Span::new(12, 14)]);
+
+        let word = cap(Pattern(char::is_alphabetic) * (1..));
+        let words = ((word & ' ') * ..).repeated();
+        let caps = words.matches("ab cd ").next().unwrap();
+        assert_eq!(caps.spans(), [Span::new(0, 6), Span::new(0, 2), Span::new(3, 5)]);
+
+        let empty = (digit * ..).repeated();
+        let caps = empty.matches("x").next().unwrap();
+        assert!(caps.span().is_empty());
+
+        let number = digit * (1..);
+        let indices: Vec<(usize, &str)> = number.match_indices("n12 - 345").collect();
+        assert_eq!(indices, [(1, "12"), (6, "345")]);
+    }
+
     #[test]
     fn combine_patterns() {
         let a = Pattern("a") | "b" | "c";
@@ -1,6 +1,8 @@
 
 use std::ops::{RangeBounds, Bound};
-use crate::capture::{CaptureResult, Capture};
+use crate::haystack::Haystack;
+use crate::capture::{CaptureResult, Capture, CaptureFn, Context};
+use crate::patterns::{Repeat, RepeatFn, repeat, repeat_width};
 
 /// Result of range pattern
 ///
@@ -15,47 +17,51 @@ use crate::capture::{CaptureResult, Capture};
 #[derive(Copy, Clone)]
 pub struct RangePattern<S, R>(pub S, pub R);
 
-impl<'a, S: Capture<'a>, R: RangeBounds<u32>> Capture<'a> for RangePattern<S, R> {
-    type Inner = S::Inner;
-
-    fn capture(&self, text: &'a str) -> Option<CaptureResult<'a, Self::Inner>> {
+impl<'a, H: ?Sized + Haystack, S: Capture<'a, H>, R: RangeBounds<u32>> Repeat<'a, H> for RangePattern<S, R> {
+    fn repeat_each(
+        &self,
+        ctx: &mut Context<'a, H>,
+        text: &'a H,
+        f: &mut RepeatFn<'_, 'a, Self::Inner, H>,
+    ) -> bool {
 
-        if self.0.capture("").is_some() && self.1.end_bound() == Bound::Unbounded {
+        let empty = text.split_at(0).0;
+        if self.0.capture(&mut Context::new(empty), empty).is_some() && self.1.end_bound() == Bound::Unbounded {
             panic!("Infinity loop")
         }
 
-        let mut count = 0;
-        let mut len = 0;
-        let mut cap = self.0.capture_empty(text);
-
-        loop {
-            match self.0.capture(cap.rest) {
-                Some(mut c) => {
-                    count += 1;
-                    len += c.captures.captured_len();
-                    c.captures.captured_str = &text[..len];
-                    cap = c;
-
-                    match self.1.end_bound() {
-                        Bound::Included(b) if *b == count     => break Some(cap),
-                        Bound::Excluded(b) if *b == count + 1 => break Some(cap),
-                        _ => {},
-                    }
-                },
-                None if self.1.contains(&count) => break Some(cap),
-                None => break None,
-            }
-        }
+        repeat(&self.0, &self.1, true, ctx, text, f)
+    }
+}
+
+impl<'a, H: ?Sized + Haystack, S: Capture<'a, H>, R: RangeBounds<u32>> Capture<'a, H> for RangePattern<S, R> {
+    type Inner = S::Inner;
+
+    fn capture_each(
+        &self,
+        ctx: &mut Context<'a, H>,
+        text: &'a H,
+        f: &mut CaptureFn<'_, 'a, Self::Inner, H>,
+    ) -> bool {
+        self.repeat_each(ctx, text, &mut |ctx, res, _| f(ctx, res))
+    }
+
+    fn capture_empty(&self, ctx: &Context<'a, H>, text: &'a H) -> CaptureResult<'a, Self::Inner, H> {
+        self.0.capture_empty(ctx, text)
+    }
+
+    fn max_width(&self) -> Option<usize> {
+        repeat_width(self.0.max_width()?, &self.1)
     }
 
-    fn capture_empty(&self, text: &'a str) -> CaptureResult<'a, Self::Inner> {
-        self.0.capture_empty(text)
+    fn max_behind(&self) -> Option<usize> {
+        self.0.max_behind()
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::super::Pattern;
+    use super::super::{Pattern, cap, backref, end};
 
     #[test]
     fn range_pattern() {
@@ -94,4 +100,58 @@ mod tests {
         assert!(range_inclusive.test("ff"));
         assert!(!range_inclusive.test("fff"));
     }
+
+    #[test]
+    fn range_pattern_backtracking() {
+        let greedy = (Pattern("g") * ..) & "g";
+        assert!(greedy.test("g"));
+        assert!(greedy.test("ggg"));
+        assert!(!greedy.test(""));
+
+        let bounded = (Pattern("h") * (1..=3)) & "hh";
+        assert!(!bounded.test("hh"));
+        assert!(bounded.test("hhh"));
+        assert!(bounded.test("hhhhh"));
+        assert!(!bounded.test("hhhhhh"));
+
+        let inner = ((Pattern("i") | "ij") * (1..)) & 'j';
+        assert!(inner.test("iij"));
+        assert!(inner.test("ijij"));
+        assert!(!inner.test("ijjj"));
+    }
+
+    #[test]
+    fn range_pattern_long_input() {
+        let text = "a".repeat(1_000_000);
+
+        assert!((Pattern('a') * ..).test(&text));
+        assert!(((Pattern('a') * (1..)) & 'a').test(&text));
+        assert!(!((Pattern('a') & 'a') * ..).test(&text[1..]));
+        assert!((Pattern('a') * 1_000_000).test(&text));
+        assert!(((Pattern('a') * ..).lazy() & end()).test(&text));
+    }
+
+    #[test]
+    fn range_pattern_long_input_of_backtracking_body() {
+        let word = cap(Pattern(char::is_alphabetic) * (1..));
+        let list = (word & ',') * ..;
+        assert!(list.test(&"ab,".repeat(100_000)));
+        assert!(!(list & end()).test(&("ab,".repeat(100_000) + "a")));
+
+        let space = (Pattern(' ') | '\t') * (1..);
+        assert_eq!(space.split(&(" ".repeat(100_000) + "x")).count(), 2);
+
+        let letters = (Pattern('a') | 'b') * ..;
+        assert!(letters.test(&"a".repeat(100_000)));
+        assert!((letters & 'b').test(&("a".repeat(100_000) + "b")));
+    }
+
+    #[test]
+    fn range_pattern_groups_of_iterations() {
+        let letter = cap(Pattern(char::is_alphabetic));
+        let doubled = ((letter & backref(&letter)) * (1..)) & backref(&letter);
+        assert!(doubled.test("aabbb"));
+        assert!(!doubled.test("aabba"));
+        assert!(!doubled.test("abab"));
+    }
 }
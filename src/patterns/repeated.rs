@@ -0,0 +1,320 @@
+
+use std::ops::{Bound, RangeBounds};
+use std::sync::Arc;
+use crate::haystack::Haystack;
+use crate::capture::{Capture, CaptureResult, CaptureFn, Context};
+use crate::captures::{Captures, Repeats};
+
+/// Result of the whole repetition which ends with the `last` iteration
+///
+/// Inner captures are taken from the last iteration.
+///
+fn repeat_result<'a, S, H>(
+    pattern: &S,
+    ctx: &Context<'a, H>,
+    text: &'a H,
+    rest: &'a H,
+    last: Option<&Captures<'a, S::Inner, H>>,
+) -> CaptureResult<'a, S::Inner, H> where
+    H: ?Sized + Haystack,
+    S: Capture<'a, H>,
+{
+    match last {
+        Some(iteration) => {
+            let mut caps = iteration.clone();
+            caps.start = ctx.position(text);
+            caps.captured_str = text.split_at(text.len() - rest.len()).0;
+            CaptureResult::new(caps, rest)
+        },
+        None => pattern.capture_empty(ctx, text),
+    }
+}
+
+/// Maximum width of a repetition of a pattern of the `width` from the range of times
+pub fn repeat_width(width: usize, range: &impl RangeBounds<u32>) -> Option<usize> {
+    let count = match range.end_bound() {
+        Bound::Included(&count) => count,
+        Bound::Excluded(&count) => count.saturating_sub(1),
+        Bound::Unbounded if width == 0 => 0,
+        Bound::Unbounded => return None,
+    };
+
+    width.checked_mul(count as usize)
+}
+
+/// Callback which receives a match of repetition along with captures of its iterations
+///
+/// The iterations are shared with the repetition, so keeping them doesn't copy them.
+///
+pub type RepeatFn<'f, 'a, T, H = str> = dyn FnMut(
+    &mut Context<'a, H>,
+    CaptureResult<'a, T, H>,
+    &Arc<Vec<Captures<'a, T, H>>>,
+) -> bool + 'f;
+
+/// Iteration of a repetition which backtracking can resume
+struct Frame<'a, H: ?Sized> {
+    /// The text the iteration starts at
+    start: &'a H,
+
+    /// Index of the match of the pattern taken by the iteration
+    index: usize,
+
+    /// Number of groups in the context before the iteration
+    mark: usize,
+}
+
+/// The match of the pattern at the `index` in order of preference
+///
+/// Groups captured by the match are left in the context.
+///
+fn nth_capture<'a, S, H>(
+    pattern: &S,
+    ctx: &mut Context<'a, H>,
+    text: &'a H,
+    index: usize,
+) -> Option<CaptureResult<'a, S::Inner, H>> where
+    H: ?Sized + Haystack,
+    S: Capture<'a, H>,
+{
+    // A simple pattern has one match and leaves no groups
+    if pattern.is_simple() {
+        return match index {
+            0 => pattern.capture(ctx, text),
+            _ => None,
+        };
+    }
+
+    let mark = ctx.group_mark();
+    let mut skipped = 0;
+    let mut found = None;
+
+    pattern.capture_each(ctx, text, &mut |ctx, res| {
+        if skipped < index {
+            skipped += 1;
+            return false;
+        }
+
+        found = Some((res, ctx.groups_since(mark)));
+        true
+    });
+
+    let (res, groups) = found?;
+    ctx.push_groups(groups);
+    Some(res)
+}
+
+/// Enumerates matches of the pattern repeated a number of times from the range
+///
+/// A greedy repetition prefers more iterations, a lazy one prefers fewer.
+/// Iterations are kept on the heap, not on the call stack, so the number of them isn't limited.
+/// Every iteration remembers which match of the pattern it took,
+/// backtracking into it matches the pattern again and skips the matches tried already.
+///
+pub fn repeat<'a, S, H, R>(
+    pattern: &S,
+    range: &R,
+    greedy: bool,
+    ctx: &mut Context<'a, H>,
+    text: &'a H,
+    f: &mut RepeatFn<'_, 'a, S::Inner, H>,
+) -> bool where
+    H: ?Sized + Haystack,
+    S: Capture<'a, H>,
+    R: RangeBounds<u32>,
+{
+    let can_grow = |count: u32| match range.end_bound() {
+        Bound::Included(&max) => count < max,
+        Bound::Excluded(&max) => count + 1 < max,
+        Bound::Unbounded => true,
+    };
+
+    let base = ctx.group_mark();
+    let mut frames: Vec<Frame<'a, H>> = Vec::new();
+    let mut iterations = Arc::new(Vec::new());
+    let mut rest = text;
+    let mut index = 0;
+
+    // The repetition has just grown to the current number of iterations
+    let mut grown = true;
+
+    loop {
+        let count = frames.len() as u32;
+        let accepts = move |
+            ctx: &mut Context<'a, H>,
+            iterations: &Arc<Vec<Captures<'a, S::Inner, H>>>,
+            f: &mut RepeatFn<'_, 'a, S::Inner, H>,
+        | {
+            range.contains(&count) && f(ctx, repeat_result(pattern, ctx, text, rest, iterations.last()), iterations)
+        };
+
+        if !greedy && grown && accepts(ctx, &iterations, f) {
+            ctx.truncate_groups(base);
+            return true;
+        }
+
+        let mark = ctx.group_mark();
+        let next = if can_grow(count) { nth_capture(pattern, ctx, rest, index) } else { None };
+        if let Some(res) = next {
+            frames.push(Frame { start: rest, index, mark });
+            Arc::make_mut(&mut iterations).push(res.captures);
+            rest = res.rest;
+            index = 0;
+            grown = true;
+            continue;
+        }
+
+        if greedy && accepts(ctx, &iterations, f) {
+            ctx.truncate_groups(base);
+            return true;
+        }
+
+        // Tries the next match of the last iteration
+        match frames.pop() {
+            Some(frame) => {
+                Arc::make_mut(&mut iterations).pop();
+                ctx.truncate_groups(frame.mark);
+                rest = frame.start;
+                index = frame.index + 1;
+                grown = false;
+            },
+            None => return false,
+        }
+    }
+}
+
+/// Repetition of a pattern which can report its iterations
+pub trait Repeat<'a, H: ?Sized + Haystack = str>: Capture<'a, H> {
+
+    /// Enumerates matches of the repetition like `Capture::capture_each`
+    ///
+    /// Every match is passed to `f` along with captures of its iterations in order,
+    /// there are none if the pattern is repeated zero times.
+    ///
+    fn repeat_each(
+        &self,
+        ctx: &mut Context<'a, H>,
+        text: &'a H,
+        f: &mut RepeatFn<'_, 'a, Self::Inner, H>,
+    ) -> bool;
+}
+
+/// Repetition which keeps captures of every iteration
+///
+/// # Examples
+/// ```
+/// # use rep::{Pattern, ScanTerm, cap};
+/// let item = cap(Pattern(char::is_alphabetic) * (1..));
+/// let list = ((item & ',') * ..).repeated();
+///
+/// let caps = list.matches("a,bc,def,").next().unwrap();
+/// let items: Vec<&str> = caps.collect();
+/// assert_eq!(items, ["a,bc,def,", "a", "bc", "def"]);
+/// ```
+///
+#[derive(Copy, Clone)]
+pub struct Repeated<P>(pub P);
+
+impl<'a, H: ?Sized + Haystack, P: Repeat<'a, H>> Capture<'a, H> for Repeated<P> {
+    type Inner = Repeats<'a, P::Inner, H>;
+
+    fn capture_each(
+        &self,
+        ctx: &mut Context<'a, H>,
+        text: &'a H,
+        f: &mut CaptureFn<'_, 'a, Self::Inner, H>,
+    ) -> bool {
+        self.0.repeat_each(ctx, text, &mut |ctx, res, iterations| {
+            let caps = res.captures.with_inner(Repeats::new(Arc::clone(iterations)));
+            f(ctx, CaptureResult::new(caps, res.rest))
+        })
+    }
+
+    fn capture_empty(&self, ctx: &Context<'a, H>, text: &'a H) -> CaptureResult<'a, Self::Inner, H> {
+        CaptureResult::new(Captures::new(ctx.position(text), text.split_at(0).0, Repeats::new(Arc::new(Vec::new()))), text)
+    }
+
+    fn max_width(&self) -> Option<usize> {
+        self.0.max_width()
+    }
+
+    fn max_behind(&self) -> Option<usize> {
+        self.0.max_behind()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Pattern, cap, cap_named};
+
+    #[test]
+    fn repeated_captures() {
+        let item = cap(Pattern(char::is_alphanumeric) * (1..));
+
+        let last = (item.clone() & ',') * ..;
+        let last_caps: Vec<&str> = last.matches("a,b,c,").next().unwrap().collect();
+        assert_eq!(last_caps, ["a,b,c,", "c"]);
+
+        let all = ((item & ',') * ..).repeated();
+        let caps = all.matches("a,b,c,").next().unwrap();
+
+        let iterations: Vec<Vec<&str>> = caps.repeated().map(|it| it.collect()).collect();
+        assert_eq!(iterations, [["a,", "a"], ["b,", "b"], ["c,", "c"]]);
+
+        let all_caps: Vec<&str> = caps.collect();
+        assert_eq!(all_caps, ["a,b,c,", "a", "b", "c"]);
+    }
+
+    #[test]
+    fn repeated_kinds() {
+        let digit = cap(Pattern(|c: char| c.is_ascii_digit()));
+
+        let many = (digit * 3).repeated();
+        let caps: Vec<&str> = many.matches("1234").next().unwrap().collect();
+        assert_eq!(caps, ["123", "1", "2", "3"]);
+
+        let lazy = (digit * (2..)).lazy().repeated();
+        let caps: Vec<&str> = lazy.matches("1234").next().unwrap().collect();
+        assert_eq!(caps, ["12", "1", "2"]);
+
+        let empty = (digit * ..).repeated();
+        let caps = empty.matches("x").next().unwrap();
+        assert_eq!(caps.repeated().count(), 0);
+        assert_eq!(caps.collect::<Vec<_>>(), [""]);
+    }
+
+    #[test]
+    fn repeated_backtracking() {
+        let word = cap(Pattern(char::is_alphabetic) * (1..));
+        let words = ((word.clone() & ' ') * (1..)).repeated() & word;
+
+        let caps: Vec<&str> = words.matches("ab cd ef").next().unwrap().collect();
+        assert_eq!(caps, ["ab cd ef", "ab", "cd", "ef"]);
+    }
+
+    #[test]
+    fn repeated_named() {
+        let pair = cap_named("key", Pattern(char::is_alphabetic)) & '=' & cap_named("value", Pattern(char::is_numeric));
+        let pairs = ((pair & ';') * (1..)).repeated();
+
+        let caps = pairs.matches("a=1;b=2;").next().unwrap();
+        assert_eq!(caps.name("key"), Some("a"));
+
+        let values: Vec<Option<&str>> = caps.repeated().map(|it| it.name("value")).collect();
+        assert_eq!(values, [Some("1"), Some("2")]);
+    }
+
+    #[test]
+    fn repeated_long_input() {
+        let item = cap(Pattern(char::is_alphabetic) * (1..));
+        let list = ((item & ',') * ..).repeated();
+        let text = "ab,".repeat(100_000);
+
+        let caps = list.clone().matches(&text).next().unwrap();
+        assert_eq!(caps.repeated().count(), 100_000);
+        assert_eq!(caps.collect::<Vec<_>>().len(), 100_001);
+
+        // Every rejected match shares the iterations instead of copying them
+        assert!(!(list & ';').test(&text));
+    }
+}
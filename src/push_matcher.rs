@@ -0,0 +1,248 @@
+
+use std::marker::PhantomData;
+use crate::haystack::Haystack;
+use crate::capture::{Capture, Context};
+use crate::captures::Captures;
+
+/// State of the matching of incrementally arriving input
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Status {
+    /// The pattern matched the beginning of the input, more input can't change the match
+    Matched,
+
+    /// The pattern can't match the input, whatever follows it
+    Failed,
+
+    /// More input is needed to decide
+    Incomplete,
+}
+
+/// Push-style matcher of a pattern at the beginning of input which arrives in pieces
+///
+/// Every piece is appended to the input and the matching is decided as soon as possible:
+/// while the match depends on the input which has not arrived yet the status is `Incomplete`.
+///
+/// The backtracking matcher can't be resumed, so no matching state is carried between pieces:
+/// the pattern is matched again over the whole input received so far.
+/// Feeding an input of `n` bytes in small pieces takes time quadratic in `n`,
+/// so it suits short messages and patterns which can't be compiled, like lookarounds or backreferences.
+/// A compilable pattern should be matched with `Compiled::push_matcher`,
+/// which keeps the threads of the VM between pieces and processes every char once.
+///
+/// # Examples
+/// ```
+/// # use rep::{Pattern, ScanTerm, Status};
+/// let mut matcher = Pattern("abc").push_matcher();
+/// assert_eq!(matcher.feed("ab"), Status::Incomplete);
+/// assert_eq!(matcher.feed("cd"), Status::Matched);
+/// assert_eq!(matcher.matched(), Some("abc"));
+/// assert_eq!(matcher.rest(), "d");
+///
+/// let mut matcher = Pattern("abc").push_matcher();
+/// assert_eq!(matcher.feed("ax"), Status::Failed);
+/// ```
+///
+pub struct PushMatcher<P, H: ?Sized = str> {
+    pattern: P,
+    buffer: Vec<u8>,
+    status: Status,
+
+    /// Length of the match if the status is `Matched`
+    len: usize,
+    finished: bool,
+    haystack: PhantomData<fn(&H)>,
+}
+
+impl<P, H: ?Sized + Haystack> PushMatcher<P, H> where
+    P: for<'a> Capture<'a, H>,
+{
+    pub fn new(pattern: P) -> PushMatcher<P, H> {
+        PushMatcher {
+            pattern,
+            buffer: Vec::new(),
+            status: Status::Incomplete,
+            len: 0,
+            finished: false,
+            haystack: PhantomData,
+        }
+    }
+
+    /// Appends the piece to the input and matches the pattern again if it's not decided
+    pub fn feed(&mut self, chunk: &H) -> Status {
+        self.buffer.extend_from_slice(chunk.as_bytes());
+
+        if self.status == Status::Incomplete {
+            self.update();
+        }
+
+        self.status
+    }
+
+    /// Notes no more input follows and decides the matching
+    pub fn finish(&mut self) -> Status {
+        self.finished = true;
+
+        if self.status == Status::Incomplete {
+            self.update();
+        }
+
+        self.status
+    }
+
+    pub fn status(&self) -> Status {
+        self.status
+    }
+
+    /// All input received so far
+    pub fn input(&self) -> &H {
+        H::valid_prefix(&self.buffer).unwrap_or_else(|valid| valid)
+    }
+
+    /// The text matched by the pattern
+    pub fn matched(&self) -> Option<&H> {
+        match self.status {
+            Status::Matched => Some(self.input().split_at(self.len).0),
+            _ => None,
+        }
+    }
+
+    /// The input after the match, or the whole input if the pattern has not matched
+    pub fn rest(&self) -> &H {
+        self.input().split_at(self.len).1
+    }
+
+    /// Captures of the match
+    pub fn captures(&self) -> Option<Captures<'_, <P as Capture<'_, H>>::Inner, H>> {
+        if self.status != Status::Matched {
+            return None;
+        }
+
+        // The match doesn't depend on the input after it, so it's found again
+        let input = self.input();
+        self.pattern.capture(&mut self.context(input), input).map(|res| res.captures)
+    }
+
+    /// Drops the matched text and matches the pattern at the beginning of the rest
+    ///
+    /// It's used to match consecutive messages of a protocol.
+    ///
+    pub fn consume(&mut self) -> Status {
+        self.buffer.drain(..self.len);
+        self.status = Status::Incomplete;
+        self.len = 0;
+        self.update();
+        self.status
+    }
+
+    fn context<'a>(&self, input: &'a H) -> Context<'a, H> {
+        if self.finished {
+            Context::new(input)
+        } else {
+            Context::partial(input)
+        }
+    }
+
+    fn update(&mut self) {
+        let input = self.input();
+        let mut ctx = self.context(input);
+        let found = self.pattern.capture(&mut ctx, input).map(|res| res.captures.captured_len());
+
+        (self.status, self.len) = match found {
+            _ if ctx.hit_end() => (Status::Incomplete, 0),
+            Some(len) => (Status::Matched, len),
+            None => (Status::Failed, 0),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Pattern, cap_named, end};
+    use super::Status;
+
+    #[test]
+    fn greedy_match_waits_for_input() {
+        let number = Pattern(|c: char| c.is_ascii_digit()) * (1..);
+        let mut matcher = number.push_matcher();
+
+        assert_eq!(matcher.feed(""), Status::Incomplete);
+        assert_eq!(matcher.feed("12"), Status::Incomplete);
+        assert_eq!(matcher.feed("34"), Status::Incomplete);
+        assert_eq!(matcher.feed("5;"), Status::Matched);
+        assert_eq!(matcher.matched(), Some("12345"));
+
+        // The decided status doesn't change
+        assert_eq!(matcher.feed("6"), Status::Matched);
+        assert_eq!(matcher.rest(), ";6");
+    }
+
+    #[test]
+    fn finish_decides() {
+        let number = Pattern(|c: char| c.is_ascii_digit()) * (1..);
+
+        let mut matcher = number.clone().push_matcher();
+        matcher.feed("42");
+        assert_eq!(matcher.finish(), Status::Matched);
+        assert_eq!(matcher.matched(), Some("42"));
+
+        let mut empty = number.push_matcher();
+        assert_eq!(empty.finish(), Status::Failed);
+        assert_eq!(empty.matched(), None);
+
+        let mut whole = (Pattern("ab") & end()).push_matcher();
+        assert_eq!(whole.feed("ab"), Status::Incomplete);
+        assert_eq!(whole.finish(), Status::Matched);
+    }
+
+    #[test]
+    fn failure_is_decided_early() {
+        let mut matcher = (Pattern("GET ") | "POST ").push_matcher();
+        assert_eq!(matcher.feed("P"), Status::Incomplete);
+        assert_eq!(matcher.feed("U"), Status::Failed);
+        assert_eq!(matcher.feed("T "), Status::Failed);
+        assert_eq!(matcher.rest(), "PUT ");
+
+        let mut preferred = (Pattern("abcd") | "ab").push_matcher();
+        assert_eq!(preferred.feed("abc"), Status::Incomplete);
+        assert_eq!(preferred.feed("x"), Status::Matched);
+        assert_eq!(preferred.matched(), Some("ab"));
+    }
+
+    #[test]
+    fn pipelined_messages() {
+        let length = cap_named("len", Pattern(b'0'..=b'9') * (1..));
+        let body = cap_named("body", Pattern(|b: u8| b != b'\n') * ..);
+        let message = length & b':' & body & b'\n';
+
+        let mut matcher = message.push_matcher();
+        assert_eq!(matcher.feed(b"3:a"), Status::Incomplete);
+        assert_eq!(matcher.feed(b"bc\n5:"), Status::Matched);
+        assert_eq!(matcher.matched(), Some(&b"3:abc\n"[..]));
+
+        let caps = matcher.captures().unwrap();
+        assert_eq!(caps.name("len"), Some(&b"3"[..]));
+        assert_eq!(caps.name("body"), Some(&b"abc"[..]));
+
+        assert_eq!(matcher.consume(), Status::Incomplete);
+        assert_eq!(matcher.feed(b"hello\n"), Status::Matched);
+        assert_eq!(matcher.captures().unwrap().name("body"), Some(&b"hello"[..]));
+
+        assert_eq!(matcher.consume(), Status::Incomplete);
+        assert_eq!(matcher.finish(), Status::Failed);
+    }
+
+    #[test]
+    fn same_status_as_compiled_push_matcher() {
+        // Long input is covered by the tests of `CompiledPushMatcher`, every piece here is matched again
+        let body = Pattern('{') & (Pattern(|c: char| c != '}') * ..) & '}';
+        let compiled = body.compile().unwrap();
+        let mut expected = compiled.push_matcher();
+        let mut matcher = body.push_matcher();
+
+        for piece in ["{", "x", "", "xx", "}", "{"] {
+            assert_eq!(matcher.feed(piece), expected.feed(piece), "{:?}", piece);
+        }
+        assert_eq!(matcher.matched(), expected.matched());
+        assert_eq!(matcher.rest(), "{");
+    }
+}
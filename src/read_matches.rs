@@ -0,0 +1,399 @@
+
+use std::borrow::Borrow;
+use std::fmt;
+use std::io::{self, Read};
+use std::iter;
+use std::marker::PhantomData;
+use crate::haystack::Haystack;
+use crate::capture::{Capture, Context};
+use crate::captures::Span;
+
+/// Default number of bytes read from the reader at once
+pub const DEFAULT_CAPACITY: usize = 8 * 1024;
+
+/// Match found in a stream
+///
+/// The match owns its text, so it outlives the buffer of the stream.
+///
+pub struct StreamMatch<H: ?Sized + Haystack = str> {
+    /// Position (in bytes) of the match from the beginning of the stream
+    pub start: usize,
+    pub text: H::Owned,
+}
+
+impl<H: ?Sized + Haystack> StreamMatch<H> {
+    pub fn as_text(&self) -> &H {
+        self.text.borrow()
+    }
+
+    /// Byte offsets of the match from the beginning of the stream
+    pub fn span(&self) -> Span {
+        Span::new(self.start, self.start + self.as_text().len())
+    }
+}
+
+impl<H: ?Sized + Haystack> Clone for StreamMatch<H> {
+    fn clone(&self) -> Self {
+        StreamMatch {
+            start: self.start,
+            text: self.as_text().to_owned(),
+        }
+    }
+}
+
+impl<H: ?Sized + Haystack> fmt::Debug for StreamMatch<H> where H::Owned: fmt::Debug {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StreamMatch")
+            .field("start", &self.start)
+            .field("text", &self.text)
+            .finish()
+    }
+}
+
+enum Search {
+    /// The match is found at the range of the buffer
+    Found(usize, usize),
+
+    /// The search must be continued from the position when more input arrives
+    More(usize),
+
+    /// No more matches in the input
+    Done,
+}
+
+/// Iterator over matches of a pattern in a reader
+///
+/// The input is read in chunks and only the unmatched tail of it is kept,
+/// so the memory stays bounded unless a single match is longer than the buffer.
+/// A match which may continue after the end of the buffer is reported
+/// only when enough input is read to decide it.
+/// The pending text is searched again after the next read, which reads at least as much as it,
+/// so a long match is searched a logarithmic number of times and the search takes linear time.
+///
+/// The text before the search position which the pattern can look at (see `Capture::max_behind`)
+/// is kept along with the pending text. A pattern with an unbounded lookbehind keeps the whole input.
+///
+pub struct ReadMatches<P, R, H: ?Sized = str> {
+    pattern: P,
+    reader: R,
+    capacity: usize,
+    buffer: Vec<u8>,
+
+    /// Number of chars kept before the search position, all of them if it's `None`
+    behind: Option<usize>,
+
+    /// Position of the beginning of the buffer in the stream
+    offset: usize,
+
+    /// Position in the buffer to continue the search from
+    pos: usize,
+
+    /// The last match is empty and ends at `pos`, so the search continues from the next char
+    skip: bool,
+    eof: bool,
+    done: bool,
+    haystack: PhantomData<fn(&H)>,
+}
+
+impl<P, R: Read, H: ?Sized + Haystack> ReadMatches<P, R, H> where
+    P: for<'a> Capture<'a, H>,
+{
+    pub fn new(pattern: P, reader: R) -> ReadMatches<P, R, H> {
+        ReadMatches::with_capacity(pattern, reader, DEFAULT_CAPACITY)
+    }
+
+    /// Reads `capacity` bytes at once
+    pub fn with_capacity(pattern: P, reader: R, capacity: usize) -> ReadMatches<P, R, H> {
+        let behind = <P as Capture<'_, H>>::max_behind(&pattern);
+
+        ReadMatches {
+            pattern,
+            reader,
+            capacity: capacity.max(1),
+            buffer: Vec::new(),
+            behind,
+            offset: 0,
+            pos: 0,
+            skip: false,
+            eof: false,
+            done: false,
+            haystack: PhantomData,
+        }
+    }
+
+    /// Searches the text, `complete` text can't be followed by more input
+    fn search(&self, text: &H, complete: bool) -> Search {
+        let mut ctx = if complete {
+            Context::new(text)
+        } else {
+            Context::partial(text)
+        };
+
+        let mut start = if self.skip {
+            match text.next_pos(self.pos) {
+                Some(start) => start,
+                None if complete => return Search::Done,
+                None => return Search::More(self.pos),
+            }
+        } else {
+            self.pos
+        };
+
+        loop {
+            ctx.reset_hit_end();
+            let found = self.pattern.capture(&mut ctx, text.split_at(start).1);
+
+            if ctx.hit_end() {
+                return Search::More(start);
+            }
+
+            if let Some(res) = found {
+                return Search::Found(start, start + res.captures.captured_len());
+            }
+
+            start = match text.next_pos(start) {
+                Some(next) => next,
+                None if complete => return Search::Done,
+                None => return Search::More(start),
+            };
+        }
+    }
+
+    /// Drops the beginning of the buffer and reads more input
+    ///
+    /// The search continues `from` the position,
+    /// the buffer is kept from the position `keep` which is not after it.
+    /// The text after `from` is searched again, so at least as much is read.
+    ///
+    fn fill(&mut self, keep: usize, from: usize) -> io::Result<()> {
+        self.buffer.drain(..keep);
+        self.offset += keep;
+        self.pos = from - keep;
+
+        let len = self.buffer.len();
+        let wanted = self.capacity.max(len - self.pos);
+        self.buffer.resize(len + wanted, 0);
+
+        let read = loop {
+            match self.reader.read(&mut self.buffer[len..]) {
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                res => break res,
+            }
+        };
+
+        match read {
+            Ok(count) => {
+                self.buffer.truncate(len + count);
+                self.eof = count == 0;
+                Ok(())
+            },
+            Err(err) => {
+                self.buffer.truncate(len);
+                Err(err)
+            },
+        }
+    }
+
+    fn fail(&mut self, err: io::Error) -> Option<io::Result<StreamMatch<H>>> {
+        self.done = true;
+        Some(Err(err))
+    }
+}
+
+impl<P, R: Read, H: ?Sized + Haystack> Iterator for ReadMatches<P, R, H> where
+    P: for<'a> Capture<'a, H>,
+{
+    type Item = io::Result<StreamMatch<H>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            // Matches before invalid bytes are still reported
+            let (text, valid) = match H::valid_prefix(&self.buffer) {
+                Ok(text) => (text, !self.eof || text.len() == self.buffer.len()),
+                Err(text) => (text, false),
+            };
+
+            match self.search(text, self.eof || !valid) {
+                Search::Found(start, end) => {
+                    let found = StreamMatch {
+                        start: self.offset + start,
+                        text: text.split_at(end).0.split_at(start).1.to_owned(),
+                    };
+
+                    self.pos = end;
+                    self.skip = start == end;
+                    return Some(Ok(found));
+                },
+                Search::More(from) => {
+                    // Keep the text before the search position the pattern looks at
+                    let keep = match self.behind {
+                        Some(behind) => iter::successors(Some(from), |&pos| text.prev_pos(pos))
+                            .take(behind.saturating_add(1))
+                            .last()
+                            .unwrap_or(from),
+                        None => 0,
+                    };
+                    self.skip &= from == self.pos;
+
+                    if let Err(err) = self.fill(keep, from) {
+                        return self.fail(err);
+                    }
+                },
+                Search::Done if !valid => {
+                    return self.fail(io::Error::new(io::ErrorKind::InvalidData, "stream is not a valid text"));
+                },
+                Search::Done => self.done = true,
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, Read};
+    use crate::{Pattern, Keywords, cap, backref, start, end, word_boundary, behind, not_behind};
+    use super::*;
+
+    fn texts<P: for<'a> Capture<'a>>(pattern: P, input: &str, capacity: usize) -> Vec<String> {
+        ReadMatches::with_capacity(pattern, input.as_bytes(), capacity)
+            .map(|m| m.unwrap().text)
+            .collect()
+    }
+
+    #[test]
+    fn matches_across_chunks() {
+        let number = Pattern(|c: char| c.is_ascii_digit()) * (1..);
+        let input = "12 345 6789 0";
+
+        for capacity in 1..8 {
+            assert_eq!(texts(number.clone(), input, capacity), ["12", "345", "6789", "0"]);
+        }
+
+        let spans: Vec<Span> = number.read_matches(input.as_bytes()).map(|m| m.unwrap().span()).collect();
+        assert_eq!(spans, [Span::new(0, 2), Span::new(3, 6), Span::new(7, 11), Span::new(12, 13)]);
+    }
+
+    #[test]
+    fn preferred_match_waits_for_input() {
+        let alternation = Pattern("abcd") | "a";
+        assert_eq!(texts(alternation, "xabcd abc", 1), ["abcd", "a"]);
+
+        let keywords = Pattern(Keywords::longest(["in", "int", "integer"]));
+        assert_eq!(texts(keywords, "integer int", 2), ["integer", "int"]);
+
+        let quote = cap(Pattern('\'') | '"');
+        let string = quote & (Pattern(|c: char| c != '\'' && c != '"') * ..) & backref(&quote);
+        assert_eq!(texts(string, "'ab' \"c\"", 1), ["'ab'", "\"c\""]);
+    }
+
+    #[test]
+    fn anchors_across_chunks() {
+        let word = word_boundary() & (Pattern(char::is_alphabetic) * (1..)) & word_boundary();
+        assert_eq!(texts(word, "ab cd", 1), ["ab", "cd"]);
+
+        let first = start() & 'a';
+        assert_eq!(texts(first, "aaa", 1), ["a"]);
+
+        let last = Pattern('a') & end();
+        assert_eq!(texts(last, "aaa", 1), ["a"]);
+    }
+
+    #[test]
+    fn lookbehind_across_chunks() {
+        let after = behind("ab") & 'x';
+        for capacity in 1..4 {
+            assert_eq!(texts(after, "abx bx abx", capacity), ["x", "x"]);
+        }
+
+        let not_after = not_behind(Pattern("ab") | "cd") & 'x';
+        assert_eq!(texts(not_after, "abx cdx bx", 1), ["x"]);
+
+        let spans: Vec<Span> = ReadMatches::with_capacity(after, "abx bx abx".as_bytes(), 1)
+            .map(|m| m.unwrap().span())
+            .collect();
+        assert_eq!(spans, [Span::new(2, 3), Span::new(9, 10)]);
+    }
+
+    #[test]
+    fn long_match_is_not_rescanned_on_every_read() {
+        struct Counted<'a>(&'a [u8], usize);
+
+        impl Read for Counted<'_> {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                self.1 += 1;
+                self.0.read(buf)
+            }
+        }
+
+        let block = Pattern('{') & (Pattern(|c: char| c != '}') * ..) & '}';
+        let input = "{".to_string() + &"x".repeat(100_000) + "}";
+        let mut matches = ReadMatches::with_capacity(block, Counted(input.as_bytes(), 0), 16);
+
+        assert_eq!(matches.next().unwrap().unwrap().span().range(), 0..100_002);
+        assert!(matches.reader.1 < 64, "{} reads", matches.reader.1);
+    }
+
+    #[test]
+    fn utf8_across_chunks() {
+        let cyrillic = Pattern('а'..='я') * (1..);
+        assert_eq!(texts(cyrillic, "xфыx ю", 1), ["фы", "ю"]);
+
+        let invalid: &[u8] = &[b'a', 0xFF, b'a'];
+        let mut matches = Pattern('a').read_matches(invalid);
+        assert_eq!(matches.next().unwrap().unwrap().text, "a");
+        assert_eq!(matches.next().unwrap().unwrap_err().kind(), io::ErrorKind::InvalidData);
+        assert!(matches.next().is_none());
+
+        let truncated: &[u8] = &[b'a', 0xD1];
+        let errors = Pattern('b').read_matches(truncated).filter(|m| m.is_err()).count();
+        assert_eq!(errors, 1);
+    }
+
+    #[test]
+    fn byte_stream() {
+        let marker = Pattern(b"\xFF\xD8");
+        let input: &[u8] = &[0x00, 0xFF, 0xD8, 0xFF, 0xFF, 0xD8];
+
+        let spans: Vec<Span> = ReadMatches::with_capacity(marker, input, 1)
+            .map(|m: io::Result<StreamMatch<[u8]>>| m.unwrap().span())
+            .collect();
+        assert_eq!(spans, [Span::new(1, 3), Span::new(4, 6)]);
+    }
+
+    #[test]
+    fn empty_matches() {
+        let spans: Vec<Span> = Pattern("").read_matches("ab".as_bytes()).map(|m| m.unwrap().span()).collect();
+        assert_eq!(spans, [Span::new(0, 0), Span::new(1, 1), Span::new(2, 2)]);
+
+        let digits = Pattern(|c: char| c.is_ascii_digit()) * ..;
+        assert_eq!(texts(digits, "a12", 1), ["", "12", ""]);
+    }
+
+    #[test]
+    fn bounded_buffer() {
+        let input = "x".repeat(100_000) + "needle";
+        let mut matches = ReadMatches::with_capacity(Pattern("needle"), input.as_bytes(), 64);
+
+        let found = matches.next().unwrap().unwrap();
+        assert_eq!(found.start, 100_000);
+        assert!(matches.buffer.len() <= 128);
+        assert!(matches.next().is_none());
+    }
+
+    #[test]
+    fn read_errors() {
+        struct Broken;
+
+        impl Read for Broken {
+            fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+                Err(io::Error::other("broken"))
+            }
+        }
+
+        let mut matches = Pattern('a').read_matches(Broken);
+        assert_eq!(matches.next().unwrap().unwrap_err().to_string(), "broken");
+        assert!(matches.next().is_none());
+    }
+}
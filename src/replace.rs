@@ -0,0 +1,232 @@
+
+use std::borrow::Cow;
+use crate::captures::{Captures, Groups};
+
+/// Replacement of matches of a pattern
+///
+/// Implemented by templates (`&str` and `String`), by `NoExpand` literals
+/// and by closures which receive captures of the match and return the replacement.
+///
+pub trait Replacer<'a, T> {
+
+    /// Appends the replacement of the match to `dst`
+    fn replace_append(&mut self, caps: &Captures<'a, T>, dst: &mut String);
+
+    /// Returns the replacement if it doesn't depend on the match
+    fn no_expansion(&mut self) -> Option<Cow<'_, str>> {
+        None
+    }
+}
+
+/// Literal replacement, `$` is not expanded in it
+///
+/// # Examples
+/// ```
+/// # use rep::{Pattern, ScanTerm, NoExpand};
+/// let price = Pattern('€');
+/// assert_eq!(price.replace_all("5€", NoExpand("$")), "5$");
+/// ```
+///
+#[derive(Copy, Clone, Debug)]
+pub struct NoExpand<'s>(pub &'s str);
+
+impl<'a, T> Replacer<'a, T> for NoExpand<'_> {
+    fn replace_append(&mut self, _: &Captures<'a, T>, dst: &mut String) {
+        dst.push_str(self.0);
+    }
+
+    fn no_expansion(&mut self) -> Option<Cow<'_, str>> {
+        Some(Cow::Borrowed(self.0))
+    }
+}
+
+impl<'a, T: Groups<'a, str> + Clone> Replacer<'a, T> for &str {
+    fn replace_append(&mut self, caps: &Captures<'a, T>, dst: &mut String) {
+        expand(self, caps, dst);
+    }
+
+    fn no_expansion(&mut self) -> Option<Cow<'_, str>> {
+        if self.contains('$') {
+            None
+        } else {
+            Some(Cow::Borrowed(*self))
+        }
+    }
+}
+
+impl<'a, T: Groups<'a, str> + Clone> Replacer<'a, T> for String {
+    fn replace_append(&mut self, caps: &Captures<'a, T>, dst: &mut String) {
+        self.as_str().replace_append(caps, dst);
+    }
+
+    fn no_expansion(&mut self) -> Option<Cow<'_, str>> {
+        if self.contains('$') {
+            None
+        } else {
+            Some(Cow::Borrowed(self.as_str()))
+        }
+    }
+}
+
+impl<'a, T, F, R> Replacer<'a, T> for F where
+    F: FnMut(&Captures<'a, T>) -> R,
+    R: AsRef<str>,
+{
+    fn replace_append(&mut self, caps: &Captures<'a, T>, dst: &mut String) {
+        dst.push_str(self(caps).as_ref());
+    }
+}
+
+/// Reference to a group in a template
+enum GroupRef<'t> {
+    Index(usize),
+    Name(&'t str),
+}
+
+/// Parses the group reference after `$`, returns it with the rest of template after it
+fn group_ref(template: &str) -> Option<(GroupRef<'_>, &str)> {
+    let (name, rest) = match template.strip_prefix('{') {
+        Some(braced) => {
+            let end = braced.find('}')?;
+            (&braced[..end], &braced[end + 1..])
+        },
+        None => {
+            let end = template
+                .find(|c: char| !c.is_alphanumeric() && c != '_')
+                .unwrap_or(template.len());
+            template.split_at(end)
+        },
+    };
+
+    if name.is_empty() {
+        return None;
+    }
+
+    match name.parse() {
+        Ok(index) => Some((GroupRef::Index(index), rest)),
+        Err(_) => Some((GroupRef::Name(name), rest)),
+    }
+}
+
+/// Expands the template with the captures
+///
+/// `$0` is the whole match, `$1`, `$2`, ... are groups in the order of iteration of captures,
+/// `$name` is the group with the name, the name can be put in braces: `${name}`.
+/// `$$` is the `$` char. A missing group is replaced with an empty string,
+/// a `$` which is not followed by a group reference is kept as is.
+///
+pub fn expand<'a, T: Groups<'a, str> + Clone>(template: &str, caps: &Captures<'a, T>, dst: &mut String) {
+    let mut rest = template;
+
+    while let Some(pos) = rest.find('$') {
+        dst.push_str(&rest[..pos]);
+        let after = &rest[pos + 1..];
+
+        if let Some(after) = after.strip_prefix('$') {
+            dst.push('$');
+            rest = after;
+            continue;
+        }
+
+        match group_ref(after) {
+            Some((group, after)) => {
+                let text = match group {
+                    GroupRef::Index(index) => caps.clone().nth(index),
+                    GroupRef::Name(name) => caps.name(name),
+                };
+
+                dst.push_str(text.unwrap_or_default());
+                rest = after;
+            },
+            None => {
+                dst.push('$');
+                rest = after;
+            },
+        }
+    }
+
+    dst.push_str(rest);
+}
+
+/// Replaces at most `limit` matches (all of them if it's zero) with the replacement
+pub fn replacen<'a, T, R>(
+    text: &'a str,
+    matches: impl Iterator<Item = Captures<'a, T>>,
+    limit: usize,
+    mut rep: R,
+) -> Cow<'a, str> where
+    R: Replacer<'a, T>,
+{
+    let limit = if limit == 0 { usize::MAX } else { limit };
+    let mut matches = matches.take(limit).peekable();
+
+    if matches.peek().is_none() {
+        return Cow::Borrowed(text);
+    }
+
+    let mut result = String::with_capacity(text.len());
+    let mut last = 0;
+
+    for caps in matches {
+        let span = caps.span();
+        result.push_str(&text[last..span.start]);
+
+        match rep.no_expansion() {
+            Some(literal) => result.push_str(&literal),
+            None => rep.replace_append(&caps, &mut result),
+        }
+
+        last = span.end;
+    }
+
+    result.push_str(&text[last..]);
+    Cow::Owned(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+    use crate::{Pattern, Captures, NoExpand, cap, cap_named};
+
+    #[test]
+    fn replace_templates() {
+        let digit = Pattern(|c: char| c.is_ascii_digit());
+        let date = cap_named("y", digit * 4) & '-' & cap_named("m", digit * 2) & '-' & cap_named("d", digit * 2);
+
+        let text = "from 2020-01-31 to 2021-12-25";
+        assert_eq!(date.replace_all(text, "$d.$m.$y"), "from 31.01.2020 to 25.12.2021");
+        assert_eq!(date.replace_all(text, "${d}th"), "from 31th to 25th");
+        assert_eq!(date.replace(text, "$3/$2/$1"), "from 31/01/2020 to 2021-12-25");
+        assert_eq!(date.replace(text, "[$0]"), "from [2020-01-31] to 2021-12-25");
+        assert_eq!(date.replace(text, "$$1 $9 $nope $ ${x"), "from $1   $ ${x to 2021-12-25");
+        assert_eq!(date.replace(text, "$y".to_string()), "from 2020 to 2021-12-25");
+    }
+
+    #[test]
+    fn replace_literals_and_closures() {
+        let word = cap(Pattern(char::is_alphabetic) * (1..));
+
+        assert_eq!(word.clone().replace_all("ab cd", NoExpand("$1")), "$1 $1");
+        assert_eq!(word.clone().replacen("ab cd ef", 2, "_"), "_ _ ef");
+
+        let upper = word.replace_all("hello, wörld", |caps: &Captures<_>| caps.clone().nth(1).unwrap().to_uppercase());
+        assert_eq!(upper, "HELLO, WÖRLD");
+
+        let mut count = 0;
+        let numbered = Pattern('x').replace_all("xyx", |_: &Captures<_>| {
+            count += 1;
+            count.to_string()
+        });
+        assert_eq!(numbered, "1y2");
+    }
+
+    #[test]
+    fn replace_borrows_unchanged_text() {
+        let missing = Pattern('z').replace_all("abc", "x");
+        assert!(matches!(missing, Cow::Borrowed("abc")));
+
+        assert_eq!(Pattern('a').replacen("aaa", 0, "b"), "bbb");
+        assert_eq!(Pattern("").replace_all("ab", "-"), "ab".replace("", "-"));
+        assert_eq!(Pattern('ф').replace_all("фыф", "f"), "fыf");
+    }
+}
@@ -1,9 +1,14 @@
 use std::ops::{Range, RangeInclusive};
+use crate::case_fold::{eq_nocase, any_case};
+use crate::haystack::Haystack;
 
 /// Object which implements ScanTerm trait
 /// is terminal element of pattern expression tree
 ///
-pub trait ScanTerm {
+/// Terms of text implement `ScanTerm<str>`,
+/// terms of binary data implement `ScanTerm<[u8]>`.
+///
+pub trait ScanTerm<H: ?Sized + Haystack = str> {
 
     /// Pattern matching function
     ///
@@ -28,11 +33,75 @@ pub trait ScanTerm {
     /// ```
     /// In that case pattern is not matching `scan` returns `None`.
     ///
-    fn scan_term(&self, text: &str) -> Option<usize>;
+    fn scan_term(&self, text: &H) -> Option<usize>;
 
-    fn scan_split<'a>(&'_ self, text: &'a str) -> Option<(&'a str, &'a str)> {
+    fn scan_split<'a>(&'_ self, text: &'a H) -> Option<(&'a H, &'a H)> {
         Some(text.split_at(self.scan_term(text)?))
     }
+
+    /// Case-insensitive pattern matching function
+    ///
+    /// Works like `scan_term`, but compares chars with simple Unicode case folding.
+    /// The result is the length of matched text, which can differ from the length of pattern.
+    ///
+    /// By default the matching is case-sensitive,
+    /// so terms which can't ignore case (like predicates) match as is.
+    /// Byte terms ignore case of ASCII letters only.
+    ///
+    /// # Examples
+    /// ```
+    /// # use rep::ScanTerm;
+    /// let pattern = "select";
+    /// assert_eq!(pattern.scan_term_nocase("SELECT *"), Some(6));
+    /// assert_eq!(pattern.scan_term_nocase("insert"), None);
+    /// ```
+    ///
+    fn scan_term_nocase(&self, text: &H) -> Option<usize> {
+        self.scan_term(text)
+    }
+
+    /// Checks the result of matching could change if more text followed the text
+    ///
+    /// It's used when the input arrives in pieces, e.g. from a stream.
+    /// By default a term matches a single char (or byte),
+    /// so only an empty text is incomplete.
+    /// Terms which look further must override it.
+    ///
+    /// # Examples
+    /// ```
+    /// # use rep::ScanTerm;
+    /// let pattern = "abc";
+    /// assert!(pattern.is_incomplete("ab", false));
+    /// assert!(!pattern.is_incomplete("ax", false));
+    /// assert!(!pattern.is_incomplete("abc", false));
+    /// assert!('a'.is_incomplete("", false));
+    /// ```
+    ///
+    fn is_incomplete(&self, text: &H, nocase: bool) -> bool {
+        let _ = nocase;
+        text.is_empty()
+    }
+
+    /// Maximum number of chars (bytes for byte terms) the term can match
+    ///
+    /// It bounds the text a lookbehind has to look through.
+    /// `None` means the width is unknown.
+    ///
+    /// # Examples
+    /// ```
+    /// # use rep::ScanTerm;
+    /// assert_eq!("фыв".term_width(), Some(3));
+    /// assert_eq!('a'.term_width(), Some(1));
+    /// ```
+    ///
+    fn term_width(&self) -> Option<usize> {
+        None
+    }
+}
+
+/// Checks the text is a proper beginning of the literal
+fn is_literal_prefix<H: ?Sized + Haystack>(literal: &H, text: &H, nocase: bool) -> bool {
+    literal.scan_prefix(text, nocase).is_some_and(|len| len < literal.len())
 }
 
 impl ScanTerm for &str {
@@ -67,12 +136,45 @@ impl ScanTerm for &str {
             None
         }
     }
+
+    fn scan_term_nocase(&self, text: &str) -> Option<usize> {
+        let mut text_chars = text.char_indices();
+
+        for ch in self.chars() {
+            match text_chars.next() {
+                Some((_, text_ch)) if eq_nocase(ch, text_ch) => {},
+                _ => return None,
+            }
+        }
+
+        Some(text_chars.next().map_or(text.len(), |(pos, _)| pos))
+    }
+
+    fn is_incomplete(&self, text: &str, nocase: bool) -> bool {
+        is_literal_prefix(*self, text, nocase)
+    }
+
+    fn term_width(&self) -> Option<usize> {
+        Some(self.chars().count())
+    }
 }
 
 impl ScanTerm for String {
     fn scan_term(&self, text: &str) -> Option<usize> {
         self.as_str().scan_term(text)
     }
+
+    fn scan_term_nocase(&self, text: &str) -> Option<usize> {
+        self.as_str().scan_term_nocase(text)
+    }
+
+    fn is_incomplete(&self, text: &str, nocase: bool) -> bool {
+        is_literal_prefix(self.as_str(), text, nocase)
+    }
+
+    fn term_width(&self) -> Option<usize> {
+        Some(self.chars().count())
+    }
 }
 
 impl ScanTerm for char {
@@ -84,6 +186,20 @@ impl ScanTerm for char {
             None
         }
     }
+
+    fn scan_term_nocase(&self, text: &str) -> Option<usize> {
+        let ch = text.chars().next()?;
+        if eq_nocase(*self, ch) {
+            Some(ch.len_utf8())
+        }
+        else {
+            None
+        }
+    }
+
+    fn term_width(&self) -> Option<usize> {
+        Some(1)
+    }
 }
 
 impl<F: Fn(char) -> bool> ScanTerm for F {
@@ -93,6 +209,98 @@ impl<F: Fn(char) -> bool> ScanTerm for F {
             _ => None,
         }
     }
+
+    fn term_width(&self) -> Option<usize> {
+        Some(1)
+    }
+}
+
+impl<const N: usize> ScanTerm for [char; N] {
+
+    /// Matches a single char which is one of the array
+    ///
+    /// A bare array is searched linearly, so it suits a few chars.
+    /// `Pattern::from(array)` builds a `CharSet` from the array once,
+    /// it looks chars up in a bitmap or by binary search.
+    ///
+    /// # Examples
+    /// ```
+    /// # use rep::{Pattern, ScanTerm};
+    /// let sign = ['+', '-'];
+    /// assert_eq!(sign.scan_term("-1"), Some(1));
+    /// assert_eq!(sign.scan_term("1"), None);
+    ///
+    /// let hex = Pattern::from(['0', '1', '2', '3', '4', '5', '6', '7', '8', '9', 'a', 'b', 'c', 'd', 'e', 'f']);
+    /// assert!((hex * (1..)).test("c0ffee"));
+    /// ```
+    ///
+    fn scan_term(&self, text: &str) -> Option<usize> {
+        let ch = text.chars().next()?;
+        if self.contains(&ch) {
+            Some(ch.len_utf8())
+        }
+        else {
+            None
+        }
+    }
+
+    fn scan_term_nocase(&self, text: &str) -> Option<usize> {
+        let ch = text.chars().next()?;
+        if any_case(ch, |other| self.contains(&other)) {
+            Some(ch.len_utf8())
+        }
+        else {
+            None
+        }
+    }
+
+    fn term_width(&self) -> Option<usize> {
+        Some(1)
+    }
+}
+
+impl ScanTerm for &[char] {
+
+    /// Matches a single char which is one of the slice
+    ///
+    /// The slice is searched linearly, so it suits a few chars.
+    /// `Pattern::from(slice)` builds a `CharSet` from the slice once.
+    ///
+    /// # Examples
+    /// ```
+    /// # use rep::{Pattern, ScanTerm};
+    /// let quotes: &[char] = &['«', '"', '»', '\''];
+    /// assert_eq!(quotes.scan_term("»"), Some(2));
+    /// assert_eq!(quotes.scan_term("a"), None);
+    ///
+    /// let digits: Vec<char> = ('0'..='9').rev().collect();
+    /// let number = Pattern::from(&digits[..]) * (1..);
+    /// assert!(number.test("2024"));
+    /// ```
+    ///
+    fn scan_term(&self, text: &str) -> Option<usize> {
+        let ch = text.chars().next()?;
+        if self.contains(&ch) {
+            Some(ch.len_utf8())
+        }
+        else {
+            None
+        }
+    }
+
+    fn scan_term_nocase(&self, text: &str) -> Option<usize> {
+        let ch = text.chars().next()?;
+        if any_case(ch, |other| self.contains(&other)) {
+            Some(ch.len_utf8())
+        }
+        else {
+            None
+        }
+    }
+
+    fn term_width(&self) -> Option<usize> {
+        Some(1)
+    }
 }
 
 impl ScanTerm for Range<char> {
@@ -105,6 +313,20 @@ impl ScanTerm for Range<char> {
             None
         }
     }
+
+    fn scan_term_nocase(&self, text: &str) -> Option<usize> {
+        let ch = text.chars().next()?;
+        if any_case(ch, |other| self.contains(&other)) {
+            Some(ch.len_utf8())
+        }
+        else {
+            None
+        }
+    }
+
+    fn term_width(&self) -> Option<usize> {
+        Some(1)
+    }
 }
 
 impl ScanTerm for RangeInclusive<char> {
@@ -117,11 +339,167 @@ impl ScanTerm for RangeInclusive<char> {
             None
         }
     }
+
+    fn scan_term_nocase(&self, text: &str) -> Option<usize> {
+        let ch = text.chars().next()?;
+        if any_case(ch, |other| self.contains(&other)) {
+            Some(ch.len_utf8())
+        }
+        else {
+            None
+        }
+    }
+
+    fn term_width(&self) -> Option<usize> {
+        Some(1)
+    }
+}
+
+impl ScanTerm<[u8]> for &[u8] {
+
+    /// ScanTerm implementation of bytes
+    ///
+    /// It checks a text starts with pattern bytes.
+    ///
+    /// # Examples
+    /// ```
+    /// # use rep::ScanTerm;
+    /// let pattern: &[u8] = b"GET";
+    /// assert_eq!(pattern.scan_term(b"GET /"), Some(3));
+    /// assert_eq!(pattern.scan_term_nocase(b"get /"), Some(3));
+    /// assert_eq!(pattern.scan_term(b"PUT /"), None);
+    /// ```
+    ///
+    fn scan_term(&self, text: &[u8]) -> Option<usize> {
+        text.scan_prefix(self, false)
+    }
+
+    fn scan_term_nocase(&self, text: &[u8]) -> Option<usize> {
+        text.scan_prefix(self, true)
+    }
+
+    fn is_incomplete(&self, text: &[u8], nocase: bool) -> bool {
+        is_literal_prefix(*self, text, nocase)
+    }
+
+    fn term_width(&self) -> Option<usize> {
+        Some(self.len())
+    }
+}
+
+impl<const N: usize> ScanTerm<[u8]> for &[u8; N] {
+    fn scan_term(&self, text: &[u8]) -> Option<usize> {
+        text.scan_prefix(*self, false)
+    }
+
+    fn scan_term_nocase(&self, text: &[u8]) -> Option<usize> {
+        text.scan_prefix(*self, true)
+    }
+
+    fn is_incomplete(&self, text: &[u8], nocase: bool) -> bool {
+        is_literal_prefix(&self[..], text, nocase)
+    }
+
+    fn term_width(&self) -> Option<usize> {
+        Some(self.len())
+    }
+}
+
+impl ScanTerm<[u8]> for Vec<u8> {
+    fn scan_term(&self, text: &[u8]) -> Option<usize> {
+        text.scan_prefix(self, false)
+    }
+
+    fn scan_term_nocase(&self, text: &[u8]) -> Option<usize> {
+        text.scan_prefix(self, true)
+    }
+
+    fn is_incomplete(&self, text: &[u8], nocase: bool) -> bool {
+        is_literal_prefix(self.as_slice(), text, nocase)
+    }
+
+    fn term_width(&self) -> Option<usize> {
+        Some(self.len())
+    }
+}
+
+impl ScanTerm<[u8]> for u8 {
+    fn scan_term(&self, text: &[u8]) -> Option<usize> {
+        match text.first() {
+            Some(byte) if byte == self => Some(1),
+            _ => None,
+        }
+    }
+
+    fn scan_term_nocase(&self, text: &[u8]) -> Option<usize> {
+        match text.first() {
+            Some(byte) if byte.eq_ignore_ascii_case(self) => Some(1),
+            _ => None,
+        }
+    }
+
+    fn term_width(&self) -> Option<usize> {
+        Some(1)
+    }
+}
+
+impl<F: Fn(u8) -> bool> ScanTerm<[u8]> for F {
+    fn scan_term(&self, text: &[u8]) -> Option<usize> {
+        match text.first() {
+            Some(&byte) if self(byte) => Some(1),
+            _ => None,
+        }
+    }
+
+    fn term_width(&self) -> Option<usize> {
+        Some(1)
+    }
+}
+
+impl ScanTerm<[u8]> for Range<u8> {
+    fn scan_term(&self, text: &[u8]) -> Option<usize> {
+        match text.first() {
+            Some(byte) if self.contains(byte) => Some(1),
+            _ => None,
+        }
+    }
+
+    fn scan_term_nocase(&self, text: &[u8]) -> Option<usize> {
+        match text.first() {
+            Some(byte) if self.contains(&byte.to_ascii_lowercase()) || self.contains(&byte.to_ascii_uppercase()) => Some(1),
+            _ => None,
+        }
+    }
+
+    fn term_width(&self) -> Option<usize> {
+        Some(1)
+    }
+}
+
+impl ScanTerm<[u8]> for RangeInclusive<u8> {
+    fn scan_term(&self, text: &[u8]) -> Option<usize> {
+        match text.first() {
+            Some(byte) if self.contains(byte) => Some(1),
+            _ => None,
+        }
+    }
+
+    fn scan_term_nocase(&self, text: &[u8]) -> Option<usize> {
+        match text.first() {
+            Some(byte) if self.contains(&byte.to_ascii_lowercase()) || self.contains(&byte.to_ascii_uppercase()) => Some(1),
+            _ => None,
+        }
+    }
+
+    fn term_width(&self) -> Option<usize> {
+        Some(1)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::patterns::Pattern;
 
     #[test]
     fn scan_str() {
@@ -211,6 +589,37 @@ mod tests {
         assert_eq!(not_a.scan_term("a"), None);
     }
 
+    #[test]
+    fn scan_char_array() {
+        let brackets = ['(', ')', '[', ']'];
+        assert_eq!(brackets.scan_term("(a)"), Some(1));
+        assert_eq!(brackets.scan_term("]"), Some(1));
+        assert_eq!(brackets.scan_term("a"), None);
+        assert_eq!(brackets.scan_term(""), None);
+
+        let quotes: &[char] = &['«', '»'];
+        assert_eq!(quotes.scan_term("«a»"), Some(2));
+        assert_eq!(quotes.scan_term("\""), None);
+
+        let unsorted: &[char] = &['z', 'm', 'a'];
+        assert_eq!(unsorted.scan_term("z"), Some(1));
+        assert_eq!(unsorted.scan_term("m"), Some(1));
+        assert_eq!(unsorted.scan_term_nocase("A"), Some(1));
+        assert_eq!(unsorted.scan_term("b"), None);
+        assert!(Pattern(unsorted).test("z"));
+
+        let mut descending: Vec<char> = ('\u{400}'..'\u{500}').step_by(2).collect();
+        descending.reverse();
+        let cyrillic: &[char] = &descending;
+        assert_eq!(cyrillic.scan_term("\u{4FE}"), Some(2));
+        assert_eq!(cyrillic.scan_term("\u{4FF}"), None);
+        assert_eq!(cyrillic.scan_term_nocase("\u{410}"), Some(2));
+        assert_eq!(cyrillic.scan_term_nocase("\u{430}"), Some(2));
+
+        let empty: [char; 0] = [];
+        assert_eq!(empty.scan_term("a"), None);
+    }
+
     #[test]
     fn scan_range() {
         let alpha = 'a'..='z';
@@ -223,4 +632,66 @@ mod tests {
         assert_eq!(binary.scan_term("1"), Some(1));
         assert_eq!(binary.scan_term("2"), None);
     }
+
+    #[test]
+    fn scan_nocase() {
+        let pattern = "Straße";
+        assert_eq!(pattern.scan_term_nocase("STRAẞE"), Some("STRAẞE".len()));
+        assert_eq!(pattern.scan_term_nocase("strasse"), None);
+
+        // Kelvin sign is three bytes long, but folds to the ASCII 'k'
+        let kelvin = "\u{212A}elvin";
+        assert_eq!("kelvin".scan_term_nocase(kelvin), Some(kelvin.len()));
+        assert_eq!("KELVIN".scan_term_nocase("kelvins"), Some(6));
+        assert_eq!("kelvin".scan_term_nocase("kel"), None);
+
+        assert_eq!('σ'.scan_term_nocase("ς"), Some(2));
+        assert_eq!('i'.scan_term_nocase("ı"), None);
+
+        let lower = 'a'..='z';
+        assert_eq!(lower.scan_term_nocase("Q"), Some(1));
+        assert_eq!(lower.scan_term_nocase("\u{212A}"), Some(3));
+        assert_eq!(lower.scan_term_nocase("1"), None);
+
+        let upper = 'A'..'T';
+        assert_eq!(upper.scan_term_nocase("ſ"), Some(2));
+        assert_eq!(upper.scan_term_nocase("z"), None);
+
+        let sign = ['x', 'ж'];
+        assert_eq!(sign.scan_term_nocase("Ж"), Some(2));
+        assert_eq!(sign.as_ref().scan_term_nocase("X"), Some(1));
+
+        let digit = |c: char| c == 'x';
+        assert_eq!(digit.scan_term_nocase("X"), None);
+    }
+
+    #[test]
+    fn scan_bytes() {
+        let pattern: &[u8] = &[0xFF, 0x00];
+        assert_eq!(pattern.scan_term(&[0xFF, 0x00, 0x01]), Some(2));
+        assert_eq!(pattern.scan_term(&[0xFF]), None);
+        assert_eq!(b"ab".scan_term(b"abc"), Some(2));
+        assert_eq!(b"".scan_term(b""), Some(0));
+        assert_eq!(b"Ab".scan_term_nocase(b"aB"), Some(2));
+        assert_eq!(vec![1, 2].scan_term(&[1, 2][..]), Some(2));
+        assert!(b"GET".is_incomplete(b"ge", true));
+        assert!(!b"GET".is_incomplete(b"ge", false));
+        assert!(!b"GET".is_incomplete(b"GET", false));
+
+        assert_eq!(0xFFu8.scan_term(&[0xFF]), Some(1));
+        assert_eq!(b'a'.scan_term(b"b"), None);
+        assert_eq!(b'a'.scan_term_nocase(b"A"), Some(1));
+        assert_eq!(b'a'.scan_term(b""), None);
+
+        let high = |b: u8| b >= 0x80;
+        assert_eq!(high.scan_term(&[0x80]), Some(1));
+        assert_eq!(high.scan_term(b"a"), None);
+
+        let lower = b'a'..=b'z';
+        assert_eq!(lower.scan_term(b"q"), Some(1));
+        assert_eq!(lower.scan_term(b"Q"), None);
+        assert_eq!(lower.scan_term_nocase(b"Q"), Some(1));
+        assert_eq!((0x00..0x20).scan_term(&[0x1F]), Some(1));
+        assert_eq!((0x00..0x20).scan_term(&[0x20]), None);
+    }
 }
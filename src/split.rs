@@ -0,0 +1,179 @@
+
+use std::collections::VecDeque;
+use crate::haystack::Haystack;
+use crate::capture::Capture;
+use crate::match_iterator::MatchIterator;
+
+/// Iterator over pieces of a text separated by matches of a pattern
+///
+/// Separators are found from left to right only, so the first call of `next_back`
+/// splits the rest of the text at once and keeps the pieces until they are taken.
+///
+pub struct Split<'a, P, H: ?Sized = str> {
+    text: &'a H,
+    matches: MatchIterator<'a, P, H>,
+
+    /// Position of the current piece
+    start: usize,
+
+    /// Number of pieces left to yield, the last of them is the rest of the text
+    limit: Option<usize>,
+
+    /// Pieces end with their separators
+    inclusive: bool,
+    done: bool,
+
+    /// Pieces left to yield, they are found when the iterator is used from the back
+    buffered: Option<VecDeque<&'a H>>,
+}
+
+impl<'a, P: Capture<'a, H>, H: ?Sized + Haystack> Split<'a, P, H> {
+    pub fn new(pattern: P, text: &'a H) -> Split<'a, P, H> {
+        Split {
+            text,
+            matches: MatchIterator::new(pattern, text),
+            start: 0,
+            limit: None,
+            inclusive: false,
+            done: false,
+            buffered: None,
+        }
+    }
+
+    /// At most `limit` pieces, the last one is the rest of the text
+    pub fn limited(pattern: P, text: &'a H, limit: usize) -> Split<'a, P, H> {
+        Split {
+            limit: Some(limit),
+            ..Split::new(pattern, text)
+        }
+    }
+
+    /// Every piece ends with the separator after it
+    pub fn inclusive(pattern: P, text: &'a H) -> Split<'a, P, H> {
+        Split {
+            inclusive: true,
+            ..Split::new(pattern, text)
+        }
+    }
+
+    fn piece(&self, end: usize) -> &'a H {
+        self.text.split_at(end).0.split_at(self.start).1
+    }
+
+    fn rest(&mut self) -> Option<&'a H> {
+        self.done = true;
+
+        if self.inclusive && self.start == self.text.len() {
+            None
+        } else {
+            Some(self.piece(self.text.len()))
+        }
+    }
+
+    /// Finds the next piece from the left
+    fn next_piece(&mut self) -> Option<&'a H> {
+        if self.done {
+            return None;
+        }
+
+        match self.limit {
+            Some(0) => {
+                self.done = true;
+                return None;
+            },
+            Some(1) => return self.rest(),
+            Some(ref mut limit) => *limit -= 1,
+            None => {},
+        }
+
+        match self.matches.next() {
+            Some(caps) => {
+                let span = caps.span();
+                let piece = self.piece(if self.inclusive { span.end } else { span.start });
+                self.start = span.end;
+                Some(piece)
+            },
+            None => self.rest(),
+        }
+    }
+}
+
+impl<'a, P: Capture<'a, H>, H: ?Sized + Haystack> Iterator for Split<'a, P, H> {
+    type Item = &'a H;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.buffered {
+            Some(ref mut pieces) => pieces.pop_front(),
+            None => self.next_piece(),
+        }
+    }
+}
+
+impl<'a, P: Capture<'a, H>, H: ?Sized + Haystack> DoubleEndedIterator for Split<'a, P, H> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.buffered.is_none() {
+            let pieces = std::iter::from_fn(|| self.next_piece()).collect();
+            self.buffered = Some(pieces);
+        }
+
+        self.buffered.as_mut().and_then(VecDeque::pop_back)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Pattern;
+
+    #[test]
+    fn split_like_str() {
+        for text in ["", "a", ",", "a,b", ",a,,b,", "ф,ы", "abc"] {
+            let expected: Vec<&str> = text.split(',').collect();
+            assert_eq!(Pattern(',').split(text).collect::<Vec<_>>(), expected);
+
+            let expected: Vec<&str> = text.rsplit(',').collect();
+            assert_eq!(Pattern(',').rsplit(text).collect::<Vec<_>>(), expected);
+
+            let expected: Vec<&str> = text.split_inclusive(',').rev().collect();
+            assert_eq!(Pattern(',').split_inclusive(text).rev().collect::<Vec<_>>(), expected);
+
+            let expected: Vec<&str> = text.split_inclusive(',').collect();
+            assert_eq!(Pattern(',').split_inclusive(text).collect::<Vec<_>>(), expected);
+
+            for limit in 0..4 {
+                let expected: Vec<&str> = text.splitn(limit, ',').collect();
+                assert_eq!(Pattern(',').splitn(text, limit).collect::<Vec<_>>(), expected);
+            }
+
+            let expected: Vec<&str> = text.split("").collect();
+            assert_eq!(Pattern("").split(text).collect::<Vec<_>>(), expected);
+        }
+    }
+
+    #[test]
+    fn split_by_combinators() {
+        let space = Pattern(char::is_whitespace) * (1..);
+        let words: Vec<&str> = space.split(" a \t b\n").collect();
+        assert_eq!(words, ["", "a", "b", ""]);
+
+        let comma = Pattern(',') & (Pattern(' ') * ..);
+        let pieces: Vec<&str> = comma.splitn("a, b,  c", 2).collect();
+        assert_eq!(pieces, ["a", "b,  c"]);
+
+        let lines: Vec<&str> = (Pattern("\r\n") | '\n').split_inclusive("a\r\nb").collect();
+        assert_eq!(lines, ["a\r\n", "b"]);
+
+        let mut both_ends = Pattern(',').split("a,b,c,d");
+        assert_eq!(both_ends.next(), Some("a"));
+        assert_eq!(both_ends.next_back(), Some("d"));
+        assert_eq!(both_ends.next(), Some("b"));
+        assert_eq!(both_ends.next_back(), Some("c"));
+        assert_eq!(both_ends.next(), None);
+        assert_eq!(both_ends.next_back(), None);
+
+        let last: Vec<&str> = comma.splitn("a, b,  c", 2).rev().collect();
+        assert_eq!(last, ["b,  c", "a"]);
+
+        let fields: Vec<&[u8]> = Pattern(0u8).split(&b"ab\0\0c"[..]).collect();
+        assert_eq!(fields, [&b"ab"[..], &b""[..], &b"c"[..]]);
+    }
+}
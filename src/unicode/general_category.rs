@@ -0,0 +1,165 @@
+
+use crate::scan_term::ScanTerm;
+use super::{tables, in_ranges, scan_char};
+
+/// Unicode general category
+///
+/// Two-letter variants are the categories themselves,
+/// one-letter variants (and `LC`) are groups of categories with the same first letter.
+///
+/// # Examples
+/// ```
+/// # use rep::{Pattern, ScanTerm, GeneralCategory};
+/// let upper = Pattern(GeneralCategory::Lu);
+/// let lower = Pattern(GeneralCategory::Ll);
+/// let word = upper & lower * (1..);
+///
+/// assert!(word.test("Hello"));
+/// assert!(word.test("Привет"));
+/// assert!(!word.test("hello"));
+/// ```
+///
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum GeneralCategory {
+    /// Uppercase letter
+    Lu,
+    /// Lowercase letter
+    Ll,
+    /// Titlecase letter
+    Lt,
+    /// Modifier letter
+    Lm,
+    /// Other letter
+    Lo,
+    /// Nonspacing mark
+    Mn,
+    /// Spacing mark
+    Mc,
+    /// Enclosing mark
+    Me,
+    /// Decimal number
+    Nd,
+    /// Letter number
+    Nl,
+    /// Other number
+    No,
+    /// Connector punctuation
+    Pc,
+    /// Dash punctuation
+    Pd,
+    /// Open punctuation
+    Ps,
+    /// Close punctuation
+    Pe,
+    /// Initial punctuation
+    Pi,
+    /// Final punctuation
+    Pf,
+    /// Other punctuation
+    Po,
+    /// Math symbol
+    Sm,
+    /// Currency symbol
+    Sc,
+    /// Modifier symbol
+    Sk,
+    /// Other symbol
+    So,
+    /// Space separator
+    Zs,
+    /// Line separator
+    Zl,
+    /// Paragraph separator
+    Zp,
+    /// Control
+    Cc,
+    /// Format
+    Cf,
+    /// Surrogate, never matches since a surrogate is not a char
+    Cs,
+    /// Private use
+    Co,
+    /// Unassigned
+    Cn,
+    /// Cased letter: `Lu`, `Ll` or `Lt`
+    LC,
+    /// Letter
+    L,
+    /// Mark
+    M,
+    /// Number
+    N,
+    /// Punctuation
+    P,
+    /// Symbol
+    S,
+    /// Separator
+    Z,
+    /// Other
+    C,
+}
+
+impl GeneralCategory {
+
+    /// Ranges of every category in the group
+    fn tables(self) -> &'static [&'static [(char, char)]] {
+        use GeneralCategory::*;
+
+        match self {
+            Lu => &[tables::LU],
+            Ll => &[tables::LL],
+            Lt => &[tables::LT],
+            Lm => &[tables::LM],
+            Lo => &[tables::LO],
+            Mn => &[tables::MN],
+            Mc => &[tables::MC],
+            Me => &[tables::ME],
+            Nd => &[tables::ND],
+            Nl => &[tables::NL],
+            No => &[tables::NO],
+            Pc => &[tables::PC],
+            Pd => &[tables::PD],
+            Ps => &[tables::PS],
+            Pe => &[tables::PE],
+            Pi => &[tables::PI],
+            Pf => &[tables::PF],
+            Po => &[tables::PO],
+            Sm => &[tables::SM],
+            Sc => &[tables::SC],
+            Sk => &[tables::SK],
+            So => &[tables::SO],
+            Zs => &[tables::ZS],
+            Zl => &[tables::ZL],
+            Zp => &[tables::ZP],
+            Cc => &[tables::CC],
+            Cf => &[tables::CF],
+            Cs => &[tables::CS],
+            Co => &[tables::CO],
+            Cn => &[tables::CN],
+            LC => &[tables::LU, tables::LL, tables::LT],
+            L => &[tables::LU, tables::LL, tables::LT, tables::LM, tables::LO],
+            M => &[tables::MN, tables::MC, tables::ME],
+            N => &[tables::ND, tables::NL, tables::NO],
+            P => &[tables::PC, tables::PD, tables::PS, tables::PE, tables::PI, tables::PF, tables::PO],
+            S => &[tables::SM, tables::SC, tables::SK, tables::SO],
+            Z => &[tables::ZS, tables::ZL, tables::ZP],
+            C => &[tables::CC, tables::CF, tables::CS, tables::CO, tables::CN],
+        }
+    }
+
+    /// Checks the char belongs to the category
+    pub fn contains(self, ch: char) -> bool {
+        self.tables().iter().any(|ranges| in_ranges(ranges, ch))
+    }
+}
+
+impl ScanTerm for GeneralCategory {
+    fn scan_term(&self, text: &str) -> Option<usize> {
+        scan_char(text, |ch| self.contains(ch))
+    }
+
+    fn term_width(&self) -> Option<usize> {
+        Some(1)
+    }
+}
@@ -0,0 +1,122 @@
+
+mod tables;
+mod general_category;
+mod property;
+mod script;
+
+pub use tables::{Script, UNICODE_VERSION};
+pub use general_category::GeneralCategory;
+pub use property::Property;
+
+/// Checks the char is in one of sorted inclusive ranges
+fn in_ranges(ranges: &[(char, char)], ch: char) -> bool {
+    ranges
+        .binary_search_by(|&(start, end)| {
+            if end < ch {
+                std::cmp::Ordering::Less
+            } else if start > ch {
+                std::cmp::Ordering::Greater
+            } else {
+                std::cmp::Ordering::Equal
+            }
+        })
+        .is_ok()
+}
+
+/// Scans a char which is in the class
+fn scan_char(text: &str, is_in: impl Fn(char) -> bool) -> Option<usize> {
+    match text.chars().next() {
+        Some(ch) if is_in(ch) => Some(ch.len_utf8()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::patterns::Pattern;
+
+    #[test]
+    fn general_categories() {
+        assert!(GeneralCategory::Lu.contains('A'));
+        assert!(GeneralCategory::Lu.contains('Ж'));
+        assert!(!GeneralCategory::Lu.contains('a'));
+        assert!(GeneralCategory::Ll.contains('ß'));
+        assert!(GeneralCategory::Lt.contains('ǅ'));
+        assert!(GeneralCategory::Nd.contains('٣'));
+        assert!(!GeneralCategory::Nd.contains('Ⅻ'));
+        assert!(GeneralCategory::Nl.contains('Ⅻ'));
+        assert!(GeneralCategory::Pc.contains('_'));
+        assert!(GeneralCategory::Pc.contains('‿'));
+        assert!(GeneralCategory::Zs.contains('\u{3000}'));
+        assert!(GeneralCategory::Cn.contains('\u{378}'));
+        assert!(GeneralCategory::Co.contains('\u{E000}'));
+        assert!(GeneralCategory::Co.contains('\u{10FFFD}'));
+
+        assert!(GeneralCategory::L.contains('字'));
+        assert!(GeneralCategory::LC.contains('ǅ'));
+        assert!(!GeneralCategory::LC.contains('字'));
+        assert!(GeneralCategory::P.contains('«'));
+        assert!(GeneralCategory::S.contains('€'));
+        assert!(GeneralCategory::C.contains('\u{0}'));
+    }
+
+    #[test]
+    fn every_char_has_one_category() {
+        use GeneralCategory::*;
+
+        let categories = [
+            Lu, Ll, Lt, Lm, Lo, Mn, Mc, Me, Nd, Nl, No, Pc, Pd, Ps, Pe, Pi, Pf, Po,
+            Sm, Sc, Sk, So, Zs, Zl, Zp, Cc, Cf, Cs, Co, Cn,
+        ];
+
+        for ch in ['\u{0}', 'a', '\u{D7FF}', '\u{E000}', '\u{FFFF}', '\u{10FFFF}'].iter().copied()
+            .chain(('\u{0}'..='\u{3000}').step_by(7))
+        {
+            let count = categories.iter().filter(|category| category.contains(ch)).count();
+            assert_eq!(count, 1, "{:?}", ch);
+        }
+    }
+
+    #[test]
+    fn scripts() {
+        assert!(Script::Latin.contains('a'));
+        assert!(Script::Cyrillic.contains('ж'));
+        assert!(Script::Greek.contains('λ'));
+        assert!(Script::Han.contains('漢'));
+        assert!(Script::Hiragana.contains('ひ'));
+        assert!(Script::Arabic.contains('ع'));
+        assert!(Script::Common.contains('1'));
+        assert!(Script::Inherited.contains('\u{301}'));
+        assert!(Script::Unknown.contains('\u{378}'));
+        assert!(!Script::Latin.contains('ж'));
+    }
+
+    #[test]
+    fn properties() {
+        assert!(Property::XidStart.contains('a'));
+        assert!(!Property::XidStart.contains('_'));
+        assert!(!Property::XidStart.contains('1'));
+        assert!(Property::XidContinue.contains('_'));
+        assert!(Property::XidContinue.contains('1'));
+        assert!(Property::XidContinue.contains('\u{301}'));
+        assert!(Property::WhiteSpace.contains('\u{2028}'));
+        assert!(Property::Alphabetic.contains('Ⅻ'));
+    }
+
+    #[test]
+    fn multilingual_identifiers() {
+        let start = Pattern(Property::XidStart) | '_';
+        let ident = start & (Pattern(Property::XidContinue) * ..);
+        assert!(ident.test("_tmp"));
+        assert!(ident.test("переменная1"));
+        assert!(ident.test("変数"));
+        assert!(ident.test("café"));
+        assert!(!ident.test("1st"));
+        assert!(!ident.test("a-b"));
+
+        let cyrillic = (Pattern(Script::Cyrillic) * (1..)) & ' ' & (Pattern(Script::Latin) * (1..));
+        assert!(cyrillic.test("слово word"));
+        assert!(!cyrillic.test("word слово"));
+    }
+}
@@ -0,0 +1,57 @@
+
+use crate::scan_term::ScanTerm;
+use super::{tables, in_ranges, scan_char};
+
+/// Unicode binary property
+///
+/// # Examples
+/// ```
+/// # use rep::{Pattern, ScanTerm, Property};
+/// let identifier = Pattern(Property::XidStart) & Pattern(Property::XidContinue) * ..;
+///
+/// assert!(identifier.test("größe_2"));
+/// assert!(identifier.test("變數"));
+/// assert!(!identifier.test("2x"));
+/// ```
+///
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Property {
+    Alphabetic,
+    Lowercase,
+    Uppercase,
+    WhiteSpace,
+    IdStart,
+    IdContinue,
+    XidStart,
+    XidContinue,
+}
+
+impl Property {
+    pub fn ranges(self) -> &'static [(char, char)] {
+        match self {
+            Property::Alphabetic => tables::ALPHABETIC,
+            Property::Lowercase => tables::LOWERCASE,
+            Property::Uppercase => tables::UPPERCASE,
+            Property::WhiteSpace => tables::WHITE_SPACE,
+            Property::IdStart => tables::ID_START,
+            Property::IdContinue => tables::ID_CONTINUE,
+            Property::XidStart => tables::XID_START,
+            Property::XidContinue => tables::XID_CONTINUE,
+        }
+    }
+
+    /// Checks the char has the property
+    pub fn contains(self, ch: char) -> bool {
+        in_ranges(self.ranges(), ch)
+    }
+}
+
+impl ScanTerm for Property {
+    fn scan_term(&self, text: &str) -> Option<usize> {
+        scan_char(text, |ch| self.contains(ch))
+    }
+
+    fn term_width(&self) -> Option<usize> {
+        Some(1)
+    }
+}
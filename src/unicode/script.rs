@@ -0,0 +1,30 @@
+
+use crate::scan_term::ScanTerm;
+use super::{Script, in_ranges, scan_char};
+
+impl Script {
+
+    /// Checks the char belongs to the script
+    ///
+    /// # Examples
+    /// ```
+    /// # use rep::{Pattern, ScanTerm, Script};
+    /// let cyrillic = Pattern(Script::Cyrillic) * (1..);
+    /// let words: Vec<&str> = cyrillic.matched_strs("hello мир!").collect();
+    /// assert_eq!(words, ["мир"]);
+    /// ```
+    ///
+    pub fn contains(self, ch: char) -> bool {
+        in_ranges(self.ranges(), ch)
+    }
+}
+
+impl ScanTerm for Script {
+    fn scan_term(&self, text: &str) -> Option<usize> {
+        scan_char(text, |ch| self.contains(ch))
+    }
+
+    fn term_width(&self) -> Option<usize> {
+        Some(1)
+    }
+}
@@ -0,0 +1,77 @@
+#!/usr/bin/env perl
+
+# Generates src/unicode/tables.rs from the Unicode database bundled with Perl
+#
+#     perl tools/unicode_tables.pl > src/unicode/tables.rs
+
+use strict;
+use warnings;
+use Unicode::UCD qw(prop_invlist prop_values prop_value_aliases);
+
+my @categories = qw(
+    Lu Ll Lt Lm Lo Mn Mc Me Nd Nl No Pc Pd Ps Pe Pi Pf Po
+    Sm Sc Sk So Zs Zl Zp Cc Cf Cs Co Cn
+);
+
+my @properties = (
+    ['ALPHABETIC', 'Alphabetic'],
+    ['LOWERCASE', 'Lowercase'],
+    ['UPPERCASE', 'Uppercase'],
+    ['WHITE_SPACE', 'White_Space'],
+    ['ID_START', 'ID_Start'],
+    ['ID_CONTINUE', 'ID_Continue'],
+    ['XID_START', 'XID_Start'],
+    ['XID_CONTINUE', 'XID_Continue'],
+);
+
+# Inclusive ranges of the property, surrogates can't be chars so they are cut off
+sub ranges {
+    my @list = prop_invlist(shift);
+    push @list, 0x110000 if @list % 2;
+
+    my @ranges;
+    while (my ($start, $end) = splice @list, 0, 2) {
+        $end -= 1;
+        push @ranges, [$start, 0xD7FF] if $start < 0xD800 && $end >= 0xD800;
+        push @ranges, [0xE000, $end] if $end > 0xDFFF && $start <= 0xDFFF;
+        push @ranges, [$start, $end] if $end < 0xD800 || $start > 0xDFFF;
+    }
+
+    return @ranges;
+}
+
+sub table {
+    my ($name, $prop) = @_;
+    my @ranges = map { sprintf "('\\u{%X}', '\\u{%X}')", @$_ } ranges($prop);
+
+    my $out = "pub const $name: &[(char, char)] = &[\n";
+    while (my @line = splice @ranges, 0, 4) {
+        $out .= "    " . join(", ", @line) . ",\n";
+    }
+
+    return $out . "];\n";
+}
+
+print "\n// Generated by tools/unicode_tables.pl from Unicode ", Unicode::UCD::UnicodeVersion(), ", do not edit\n\n";
+print "pub const UNICODE_VERSION: (u8, u8, u8) = (", join(", ", split /\./, Unicode::UCD::UnicodeVersion()), ");\n\n";
+
+print table(uc $_, "gc=$_"), "\n" for @categories;
+print table($_->[0], $_->[1]), "\n" for @properties;
+
+my @scripts = sort map { (prop_value_aliases('Script', $_))[1] } prop_values('Script');
+
+print "/// Unicode script\n";
+print "#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]\n";
+print "pub enum Script {\n";
+print "    ", s/_//gr, ",\n" for @scripts;
+print "}\n\n";
+
+print "impl Script {\n";
+print "    pub fn ranges(self) -> &'static [(char, char)] {\n";
+print "        match self {\n";
+print "            Script::", s/_//gr, " => ", uc, ",\n" for @scripts;
+print "        }\n";
+print "    }\n";
+print "}\n";
+
+print "\n", table(uc, "Script=$_") for @scripts;